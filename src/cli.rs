@@ -1,8 +1,18 @@
 use std::{io, path::PathBuf};
 
-use clap::{Parser, Subcommand};
+use chrono::{Local, NaiveDate};
+use clap::{Args, Parser, Subcommand};
 
-use crate::{tidy::TidyProgram, todo::TodoProgram};
+use crate::{
+    config::{self, ConfigProgram},
+    shell::{InitProgram, Shell},
+    tidy::{projects_db::ProjectSort, TidyProgram},
+    todo::{
+        todo::{self, Priority, TodoSort},
+        TodoProgram,
+    },
+    utils::output::OutputFormat,
+};
 
 const ABOUT: &str = "Flow is a good TooKit for manage workflow of developers";
 
@@ -14,6 +24,9 @@ const ABOUT: &str = "Flow is a good TooKit for manage workflow of developers";
 pub struct Cli {
     #[command(subcommand)]
     command: Commands,
+    ///Print the lists in a format read by other programs
+    #[arg(long, global = true, value_enum)]
+    format: Option<OutputFormat>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -28,30 +41,254 @@ pub enum Commands {
         #[command(subcommand)]
         command: TodoCommands,
     },
+    ///Read and change the configuration file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    ///Print the shell function that lets `tidy cd` change the directory
+    Init {
+        ///Add `eval "$(wflow init bash)"` to your shell startup file
+        shell: Shell,
+    },
 }
 
 #[derive(Subcommand, Debug)]
 pub enum TidyCommands {
     ///Add the current path to projects
-    Add { path: PathBuf },
+    Add {
+        path: PathBuf,
+        ///Command used to open this project, like `nvim {path}`
+        #[arg(short, long)]
+        editor: Option<String>,
+    },
     ///List all projects saved
-    List,
+    List {
+        ///Only list the projects with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+        ///Order of the projects
+        #[arg(short, long, value_enum, default_value_t)]
+        sort: ProjectSort,
+        ///Show the branch, ahead/behind commits, changed files and last commit of the repositories
+        #[arg(short, long)]
+        git: bool,
+    },
+    ///Change the name, aliases, tags or description of a project
+    Edit(EditProjectArgs),
     ///Open a project with an editor
-    Open,
+    Open {
+        ///Name or path of the project, fuzzy matched
+        query: Option<String>,
+        ///Command used to open the project this time, like `idea {path}`
+        #[arg(short, long)]
+        editor: Option<String>,
+        ///Order of the projects in the list, and of the matches with the same score
+        #[arg(short, long, value_enum, default_value_t)]
+        sort: ProjectSort,
+    },
+    ///Find the projects inside a folder and choose the ones to add
+    Scan {
+        ///Folder to search, the current one by default
+        dir: Option<PathBuf>,
+        ///Folders to go down, `tidy.scan_depth` in the config by default
+        #[arg(short, long)]
+        depth: Option<usize>,
+        ///Add all the projects found without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+    ///Report the projects that are missing, moved, duplicated or not a folder
+    Doctor,
+    ///Remove the projects reported by `doctor`, and fix the moved ones
+    Prune {
+        ///Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    ///Print the path of a project, `wflow init` makes it change the directory
+    Cd {
+        ///Name or path of the project, fuzzy matched
+        query: Option<String>,
+        ///Write the path to this file instead of printing it
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
     ///Create new folder project and save
-    New,
-    ///Remove a project
+    New {
+        ///Name of the folder, asked when missing
+        name: Option<String>,
+        ///Folder where the project is created, the current one by default
+        #[arg(short, long)]
+        parent: Option<PathBuf>,
+        ///Template of the config folder to copy in the project
+        #[arg(short, long)]
+        template: Option<String>,
+    },
+    ///Remove a project, its folder can be moved to the trash
     Remove,
+    ///Bring back a removed project, and its folder from the trash
+    Restore,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum TodoCommands {
-    ///List all todos
-    List,
+    ///List the todos of the current project, or all of them
+    List {
+        ///List all the todos, not only the ones of the current project
+        #[arg(short, long)]
+        global: bool,
+        ///Order of the todos
+        #[arg(short, long, value_enum, default_value_t)]
+        sort: TodoSort,
+        ///Only list the todos with this tag, or without it as `!tag`. Can be repeated
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+    },
     ///Create new todo and save
-    Create,
-    ///Change the todo status
-    Check,
+    Create(CreateTodoArgs),
+    ///Check a todo by id or description prefix, or choose the status of each todo
+    Check {
+        query: Option<String>,
+        ///Show all the todos, not only the ones of the current project
+        #[arg(short, long)]
+        global: bool,
+    },
+    ///Mark a todo as not started, by id or description prefix
+    Uncheck { query: String },
+    ///Toggle the status of a todo, by id or description prefix
+    Toggle { query: String },
+    ///Show the open todos with a due date: overdue, today, this week and later
+    Agenda {
+        ///Show all the todos, not only the ones of the current project
+        #[arg(short, long)]
+        global: bool,
+    },
+    ///List the tags of the todos, with how many are open and completed
+    Tags {
+        ///Count all the todos, not only the ones of the current project
+        #[arg(short, long)]
+        global: bool,
+    },
+    ///Change the text of a todo, by id or description prefix
+    Edit {
+        query: String,
+        ///Write the text with $VISUAL or $EDITOR instead of the inline input
+        #[arg(short, long)]
+        editor: bool,
+    },
+    ///Delete todos, by id or description prefix
+    Delete {
+        #[arg(required = true)]
+        queries: Vec<String>,
+    },
+    ///List or restore the completed todos archived by `check`
+    Archive {
+        #[command(subcommand)]
+        command: ArchiveCommands,
+    },
+    ///Change the priority of a todo, by id or description prefix
+    Priority {
+        query: String,
+        ///p0 to p3, or critical, high, medium and low
+        priority: Priority,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ArchiveCommands {
+    ///List the archived todos of the current project, or all of them
+    List {
+        ///List all the archived todos, not only the ones of the current project
+        #[arg(short, long)]
+        global: bool,
+        ///Only the todos completed since this date: YYYY-MM-DD, today, yesterday, 3d or 2w
+        #[arg(short, long, value_parser = parse_since)]
+        since: Option<NaiveDate>,
+    },
+    ///Move an archived todo back to the todos
+    Restore { id: u32 },
+}
+
+#[derive(Args, Debug)]
+pub struct EditProjectArgs {
+    ///Name or path of the project, fuzzy matched
+    pub query: Option<String>,
+    ///Name shown for the project, an empty name restores the folder name
+    #[arg(short, long)]
+    pub name: Option<String>,
+    ///Add an alias, can be repeated
+    #[arg(short, long = "alias")]
+    pub aliases: Vec<String>,
+    ///Remove an alias, can be repeated
+    #[arg(long = "remove-alias")]
+    pub remove_aliases: Vec<String>,
+    ///Add a tag, can be repeated
+    #[arg(short, long = "tag")]
+    pub tags: Vec<String>,
+    ///Remove a tag, can be repeated
+    #[arg(long = "remove-tag")]
+    pub remove_tags: Vec<String>,
+    ///Description of the project, an empty description removes it
+    #[arg(short, long)]
+    pub description: Option<String>,
+    ///Command used to open the project, an empty command removes it
+    #[arg(short, long)]
+    pub editor: Option<String>,
+}
+
+impl EditProjectArgs {
+    /// Whether some change was asked, otherwise the project is only shown.
+    pub fn has_changes(&self) -> bool {
+        self.name.is_some()
+            || !self.aliases.is_empty()
+            || !self.remove_aliases.is_empty()
+            || !self.tags.is_empty()
+            || !self.remove_tags.is_empty()
+            || self.description.is_some()
+            || self.editor.is_some()
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    ///Print the value of a key, like `tidy.editor`
+    Get { key: String },
+    ///Change the value of a key
+    Set { key: String, value: String },
+    ///List all the keys with their values
+    List,
+    ///Open the configuration file with $VISUAL or $EDITOR
+    Edit,
+}
+
+#[derive(Args, Debug)]
+pub struct CreateTodoArgs {
+    ///Text of the todo, asked interactively when missing
+    pub text: Option<String>,
+    ///Priority: p0-p3 or critical/high/medium/low
+    #[arg(short, long)]
+    pub priority: Option<Priority>,
+    ///Tag of the todo, can be repeated. The #tags of the text are added too
+    #[arg(short, long = "tag")]
+    pub tags: Vec<String>,
+    ///Due date: YYYY-MM-DD, today, tomorrow, +3d, +2w or a day like fri
+    #[arg(short, long, value_parser = parse_due)]
+    pub due: Option<NaiveDate>,
+    ///Saved project (path or folder name) the todo belongs to, defaults to the current one
+    #[arg(long)]
+    pub project: Option<String>,
+    ///Don't attach the todo to the current project
+    #[arg(short, long, conflicts_with = "project")]
+    pub global: bool,
+}
+
+fn parse_due(text: &str) -> Result<NaiveDate, String> {
+    todo::parse_due_date(text, Local::now().date_naive()).map_err(|e| e.to_string())
+}
+
+fn parse_since(text: &str) -> Result<NaiveDate, String> {
+    todo::parse_since_date(text, Local::now().date_naive()).map_err(|e| e.to_string())
 }
 
 pub struct App;
@@ -59,9 +296,16 @@ pub struct App;
 impl App {
     pub fn run() -> io::Result<()> {
         let cli = Cli::parse();
+        // A broken config file can still be fixed with the config subcommand,
+        // and must not break the shells that run `wflow init` when they start
+        if !matches!(cli.command, Commands::Config { .. } | Commands::Init { .. }) {
+            config::init()?;
+        }
         match &cli.command {
-            Commands::Tidy { command } => TidyProgram::run(command),
-            Commands::Todo { command } => TodoProgram::run(command),
+            Commands::Tidy { command } => TidyProgram::run(command, cli.format),
+            Commands::Todo { command } => TodoProgram::run(command, cli.format),
+            Commands::Config { command } => ConfigProgram::run(command),
+            Commands::Init { shell } => InitProgram::run(*shell),
         }
     }
 }
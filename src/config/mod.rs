@@ -0,0 +1,452 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    fs,
+    io::{self, stdout, Stdout},
+    path::PathBuf,
+    sync::OnceLock,
+};
+
+use cli_printer::styles::ICON_CHECK;
+use crossterm::{
+    execute,
+    style::{Color, Print, Stylize},
+};
+use toml_edit::DocumentMut;
+
+use crate::{
+    cli::ConfigCommands,
+    constants,
+    todo::todo::{Priority, TodoState},
+    utils,
+};
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Clone, Copy)]
+enum KeyKind {
+    Text,
+    Path,
+    Number,
+    Priority,
+    Color,
+}
+
+struct ConfigKey {
+    name: &'static str,
+    kind: KeyKind,
+    default: &'static str,
+    about: &'static str,
+}
+
+const SECTIONS: [&str; 3] = ["tidy", "todo", "ui"];
+
+const KEYS: [ConfigKey; 15] = [
+    ConfigKey {
+        name: "tidy.editor",
+        kind: KeyKind::Text,
+        default: "",
+        about: "Command used to open a project, like `nvim {path}`",
+    },
+    ConfigKey {
+        name: "tidy.projects_file",
+        kind: KeyKind::Path,
+        default: "",
+        about: "File where the projects are saved",
+    },
+    ConfigKey {
+        name: "tidy.scan_depth",
+        kind: KeyKind::Number,
+        default: "3",
+        about: "Folders that `tidy scan` goes down to find projects",
+    },
+    ConfigKey {
+        name: "todo.todos_file",
+        kind: KeyKind::Path,
+        default: "",
+        about: "File where the todos are saved",
+    },
+    ConfigKey {
+        name: "todo.default_priority",
+        kind: KeyKind::Priority,
+        default: "p2",
+        about: "Priority of the new todos",
+    },
+    ConfigKey {
+        name: "ui.icon_not_started",
+        kind: KeyKind::Text,
+        default: "[ ]",
+        about: "Icon of the todos not started",
+    },
+    ConfigKey {
+        name: "ui.icon_in_progress",
+        kind: KeyKind::Text,
+        default: "[~]",
+        about: "Icon of the todos in progress",
+    },
+    ConfigKey {
+        name: "ui.icon_blocked",
+        kind: KeyKind::Text,
+        default: "[!]",
+        about: "Icon of the blocked todos",
+    },
+    ConfigKey {
+        name: "ui.icon_completed",
+        kind: KeyKind::Text,
+        default: "[x]",
+        about: "Icon of the completed todos",
+    },
+    ConfigKey {
+        name: "ui.icon_cancelled",
+        kind: KeyKind::Text,
+        default: "[-]",
+        about: "Icon of the cancelled todos",
+    },
+    ConfigKey {
+        name: "ui.color_not_started",
+        kind: KeyKind::Color,
+        default: "none",
+        about: "Colour of the todos not started",
+    },
+    ConfigKey {
+        name: "ui.color_in_progress",
+        kind: KeyKind::Color,
+        default: "yellow",
+        about: "Colour of the todos in progress",
+    },
+    ConfigKey {
+        name: "ui.color_blocked",
+        kind: KeyKind::Color,
+        default: "red",
+        about: "Colour of the blocked todos",
+    },
+    ConfigKey {
+        name: "ui.color_completed",
+        kind: KeyKind::Color,
+        default: "green",
+        about: "Colour of the completed todos",
+    },
+    ConfigKey {
+        name: "ui.color_cancelled",
+        kind: KeyKind::Color,
+        default: "dark_grey",
+        about: "Colour of the cancelled todos",
+    },
+];
+
+#[derive(Debug)]
+pub struct ConfigError {
+    pub key: String,
+    pub message: String,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid config key `{}`: {}", self.key, self.message)
+    }
+}
+
+impl From<ConfigError> for io::Error {
+    fn from(e: ConfigError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+    }
+}
+
+fn find_key(name: &str) -> Result<&'static ConfigKey, ConfigError> {
+    KEYS.iter()
+        .find(|k| k.name == name)
+        .ok_or_else(|| ConfigError {
+            key: name.to_string(),
+            message: "unknown key, run `wflow config list` to see all the keys".to_string(),
+        })
+}
+
+fn parse_color(value: &str) -> Option<Option<Color>> {
+    match value {
+        "none" => Some(None),
+        _ => Color::try_from(value).ok().map(Some),
+    }
+}
+
+fn validate_value(key: &ConfigKey, value: &str) -> Result<(), ConfigError> {
+    let message = match key.kind {
+        KeyKind::Text | KeyKind::Path if value.trim().is_empty() => {
+            Some("the value cannot be empty".to_string())
+        }
+        KeyKind::Number if !value.parse::<u32>().is_ok_and(|n| n > 0) => {
+            Some(format!("'{}' is not a number greater than 0", value))
+        }
+        KeyKind::Priority => value.parse::<Priority>().err().map(|e| e.to_string()),
+        KeyKind::Color if parse_color(value).is_none() => Some(format!(
+            "unknown colour '{}', expected none or a name like red or dark_grey",
+            value
+        )),
+        _ => None,
+    };
+    match message {
+        Some(message) => Err(ConfigError {
+            key: key.name.to_string(),
+            message,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Configuration loaded from `config.toml`, every value was already validated.
+#[derive(Default)]
+pub struct Config {
+    values: HashMap<&'static str, String>,
+}
+
+impl Config {
+    fn from_document(document: &DocumentMut) -> Result<Self, ConfigError> {
+        let mut values = HashMap::new();
+        for (section, item) in document.iter() {
+            if !SECTIONS.contains(&section) {
+                return Err(ConfigError {
+                    key: section.to_string(),
+                    message: format!("unknown section, expected one of {}", SECTIONS.join(", ")),
+                });
+            }
+            let table = match item.as_table_like() {
+                Some(t) => t,
+                None => {
+                    return Err(ConfigError {
+                        key: section.to_string(),
+                        message: "expected a table".to_string(),
+                    })
+                }
+            };
+            for (name, value) in table.iter() {
+                let key = find_key(&format!("{}.{}", section, name))?;
+                let value = match (key.kind, value.as_str(), value.as_integer()) {
+                    (_, Some(v), _) => v.to_string(),
+                    (KeyKind::Number, _, Some(n)) => n.to_string(),
+                    (kind, _, _) => {
+                        let expected = match kind {
+                            KeyKind::Number => "expected a number",
+                            _ => "expected a string",
+                        };
+                        return Err(ConfigError {
+                            key: key.name.to_string(),
+                            message: expected.to_string(),
+                        });
+                    }
+                };
+                validate_value(key, &value)?;
+                values.insert(key.name, value);
+            }
+        }
+        Ok(Self { values })
+    }
+
+    /// Value of a key, or its default value when it is not configured.
+    fn value(&self, name: &str) -> &str {
+        match self.values.get(name) {
+            Some(v) => v,
+            None => KEYS
+                .iter()
+                .find(|k| k.name == name)
+                .map(|k| k.default)
+                .unwrap_or_default(),
+        }
+    }
+
+    fn path(&self, name: &str) -> Option<PathBuf> {
+        self.values.get(name).map(|p| utils::expand_home(p))
+    }
+
+    pub fn editor(&self) -> Option<&str> {
+        self.values.get("tidy.editor").map(|e| e.as_str())
+    }
+
+    pub fn projects_file(&self) -> Option<PathBuf> {
+        self.path("tidy.projects_file")
+    }
+
+    pub fn scan_depth(&self) -> usize {
+        self.value("tidy.scan_depth").parse().unwrap_or(3)
+    }
+
+    pub fn todos_file(&self) -> Option<PathBuf> {
+        self.path("todo.todos_file")
+    }
+
+    pub fn default_priority(&self) -> Priority {
+        self.value("todo.default_priority")
+            .parse()
+            .unwrap_or_default()
+    }
+
+    pub fn icon(&self, state: &TodoState) -> &str {
+        self.value(&format!("ui.icon_{}", state.key()))
+    }
+
+    pub fn color(&self, state: &TodoState) -> Option<Color> {
+        parse_color(self.value(&format!("ui.color_{}", state.key()))).flatten()
+    }
+}
+
+fn get_config_path() -> io::Result<PathBuf> {
+    let mut path = utils::get_config_folder()?;
+    path.push(constants::NAME_CONFIG_FILE);
+    Ok(path)
+}
+
+fn read_document() -> io::Result<DocumentMut> {
+    let path = get_config_path()?;
+    if !path.exists() {
+        return Ok(DocumentMut::new());
+    }
+    let content = fs::read_to_string(&path)?;
+    content.parse::<DocumentMut>().map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Cannot read {}: {}", path.display(), e),
+        )
+    })
+}
+
+fn load() -> io::Result<Config> {
+    let document = read_document()?;
+    Config::from_document(&document).map_err(|e| {
+        let path = get_config_path()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, e))
+    })
+}
+
+/// Load the configuration file, called once at startup.
+pub fn init() -> io::Result<()> {
+    let config = load()?;
+    let _ = CONFIG.set(config);
+    Ok(())
+}
+
+/// Get the loaded configuration, or the default one if `init` was not called.
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+pub struct ConfigProgram;
+
+impl ConfigProgram {
+    pub fn run(config_command: &ConfigCommands) -> io::Result<()> {
+        let mut stdout = stdout();
+        match config_command {
+            ConfigCommands::Get { key } => get_key(&mut stdout, key),
+            ConfigCommands::Set { key, value } => set_key(&mut stdout, key, value),
+            ConfigCommands::List => list_keys(&mut stdout),
+            ConfigCommands::Edit => edit_config(&mut stdout),
+        }
+    }
+}
+
+fn get_key(stdout: &mut Stdout, name: &str) -> io::Result<()> {
+    let key = find_key(name)?;
+    let config = load()?;
+    execute!(stdout, Print(format!("{}\n", config.value(key.name))))?;
+    Ok(())
+}
+
+fn set_key(stdout: &mut Stdout, name: &str, value: &str) -> io::Result<()> {
+    let key = find_key(name)?;
+    validate_value(key, value)?;
+
+    let (section, field) = key.name.split_once('.').unwrap_or_default();
+    let mut document = read_document()?;
+    if !document.contains_key(section) {
+        document[section] = toml_edit::table();
+    }
+    document[section][field] = match (key.kind, value.parse::<i64>()) {
+        (KeyKind::Number, Ok(n)) => toml_edit::value(n),
+        _ => toml_edit::value(value),
+    };
+
+    let path = get_config_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    utils::write_file(&path, &document.to_string())?;
+
+    execute!(
+        stdout,
+        Print(ICON_CHECK.green()),
+        Print(format!("{} = {}\n", key.name, value.green()))
+    )?;
+    Ok(())
+}
+
+fn list_keys(stdout: &mut Stdout) -> io::Result<()> {
+    let config = load()?;
+    for key in &KEYS {
+        let value = match config.values.get(key.name) {
+            Some(v) => v.to_string(),
+            None if key.default.is_empty() => "(not set)".dark_grey().to_string(),
+            None => format!("{} {}", key.default, "(default)".dark_grey()),
+        };
+        execute!(
+            stdout,
+            Print(format!("{} = {}\n", key.name.cyan(), value)),
+            Print(format!("    {}\n", key.about.dark_grey()))
+        )?;
+    }
+    Ok(())
+}
+
+fn edit_config(stdout: &mut Stdout) -> io::Result<()> {
+    let path = get_config_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if !path.exists() {
+        utils::write_file(&path, "[tidy]\n\n[todo]\n\n[ui]\n")?;
+    }
+
+    utils::edit_file(&path)?;
+
+    load()?;
+    execute!(
+        stdout,
+        Print(ICON_CHECK.green()),
+        Print(format!("{} is valid\n", path.display()))
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Result<Config, ConfigError> {
+        Config::from_document(&content.parse::<DocumentMut>().unwrap())
+    }
+
+    #[test]
+    fn numbers_can_be_integers_or_strings() {
+        assert_eq!(parse("[tidy]\nscan_depth = 5").unwrap().scan_depth(), 5);
+        assert_eq!(parse("[tidy]\nscan_depth = \"2\"").unwrap().scan_depth(), 2);
+        assert_eq!(parse("").unwrap().scan_depth(), 3);
+    }
+
+    #[test]
+    fn wrong_numbers_are_rejected() {
+        let error = parse("[tidy]\nscan_depth = true").err().unwrap();
+        assert_eq!(error.message, "expected a number");
+        assert!(parse("[tidy]\nscan_depth = 0").is_err());
+        assert!(parse("[tidy]\nscan_depth = -1").is_err());
+        assert!(parse("[tidy]\nscan_depth = \"deep\"").is_err());
+    }
+
+    #[test]
+    fn unknown_keys_and_values_are_rejected() {
+        let error = parse("[tidy]\neditor = 3").err().unwrap();
+        assert_eq!(error.message, "expected a string");
+        assert!(parse("[other]\nkey = \"a\"").is_err());
+        assert!(parse("[tidy]\nnope = \"a\"").is_err());
+        assert!(parse("[todo]\ndefault_priority = \"p9\"").is_err());
+        assert!(parse("[ui]\ncolor_completed = \"pink\"").is_err());
+        assert!(parse("[ui]\ncolor_completed = \"none\"").is_ok());
+    }
+}
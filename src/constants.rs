@@ -1,2 +1,23 @@
-pub const NAME_PROJECTS_FILE: &str = "projects.txt";
-pub const NAME_TODOS_FILE: &str = "todos.txt";
+pub const NAME_PROJECTS_FILE: &str = "projects.json";
+pub const NAME_LEGACY_PROJECTS_FILE: &str = "projects.txt";
+pub const NAME_TODOS_FILE: &str = "todos.json";
+pub const NAME_LEGACY_TODOS_FILE: &str = "todos.txt";
+/// Added to the name of the todos file for the archive of its completed todos.
+pub const ARCHIVE_FILE_SUFFIX: &str = "_archive";
+/// Projects removed with `tidy remove`, for `tidy restore`.
+pub const NAME_TRASH_FILE: &str = "trash.json";
+pub const PROGRAM_FOLDER_NAME: &str = "wflow";
+pub const ENV_DATA_DIR: &str = "WFLOW_DATA_DIR";
+pub const ENV_CONFIG_DIR: &str = "WFLOW_CONFIG_DIR";
+pub const NAME_CONFIG_FILE: &str = "config.toml";
+/// Folder of the config folder with the templates of `tidy new`.
+pub const TEMPLATES_FOLDER_NAME: &str = "templates";
+/// File of a template with its post-create commands, it is not copied.
+pub const NAME_TEMPLATE_FILE: &str = "template.toml";
+/// Files moved from the folder of the executable to the data folder.
+pub const DATA_FILES: [&str; 4] = [
+    NAME_PROJECTS_FILE,
+    NAME_LEGACY_PROJECTS_FILE,
+    NAME_TODOS_FILE,
+    NAME_LEGACY_TODOS_FILE,
+];
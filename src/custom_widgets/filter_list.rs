@@ -0,0 +1,198 @@
+use cli_printer::{
+    core::{
+        interfaces::{Widget, WidgetChild},
+        utils::{Action, IconAndLabel, RenderWidget},
+    },
+    styles::{ICON_CHECK, ICON_QUESTION},
+};
+use crossterm::{
+    event::KeyCode,
+    execute,
+    style::{Print, Stylize},
+};
+
+use crate::utils::fuzzy::fuzzy_match;
+
+use super::{highlight, read_key, text_input::is_shortcut};
+
+type AfterCb<T> = dyn FnMut(&mut FilterListData, T) -> Action;
+
+/// List to select an option, typing characters narrows the options.
+pub struct FilterList<'a, T> {
+    options: Vec<String>,
+    cancel_option: Option<&'a str>,
+    text_init: IconAndLabel<'a>,
+    text_final: IconAndLabel<'a>,
+    cb_after: Box<AfterCb<T>>,
+    local_state: FilterListData,
+}
+
+pub struct FilterListData {
+    pub is_selected: bool,
+    /// The cancel option (or Esc) was chosen instead of an option.
+    pub is_cancelled: bool,
+    pub filter: String,
+    /// Offset of the cursor in the visible options.
+    pub offset: usize,
+    pub current_option: Option<String>,
+    /// Index of the current option in the options given to `new`.
+    pub index: Option<usize>,
+}
+
+impl<'a, T: Clone> Widget for FilterList<'a, T> {
+    fn render(&mut self, stdout: &mut std::io::Stdout) -> std::io::Result<()> {
+        if self.local_state.is_selected {
+            let text_selected = match &self.local_state.current_option {
+                Some(option) if !self.local_state.is_cancelled => option.to_string(),
+                _ => self.cancel_option.unwrap_or("None").to_string(),
+            };
+            execute!(
+                stdout,
+                Print(self.text_final.0.green()),
+                Print(self.text_final.1),
+                Print(text_selected.dark_grey()),
+            )?;
+            return Ok(());
+        }
+
+        execute!(
+            stdout,
+            Print(self.text_init.0.cyan()),
+            Print(self.text_init.1),
+            Print("\n"),
+        )?;
+        let filter_text = if self.local_state.filter.is_empty() {
+            "type to filter".dark_grey().to_string()
+        } else {
+            self.local_state.filter.clone()
+        };
+        execute!(stdout, Print(format!("{} {}\n", "/".cyan(), filter_text)))?;
+
+        let visible = self.visible_options();
+        for (row, (index, matched)) in visible.iter().enumerate() {
+            let text = highlight(&self.options[*index], matched, |s| s.to_string());
+            if row == self.local_state.offset {
+                execute!(stdout, Print("> ".cyan()), Print(text))?;
+            } else {
+                execute!(stdout, Print("  "), Print(text))?;
+            }
+            execute!(stdout, Print("\n"))?;
+        }
+        if let Some(cancel) = self.cancel_option {
+            if self.local_state.offset == visible.len() {
+                execute!(stdout, Print("> ".cyan()), Print(cancel.cyan()))?;
+            } else {
+                execute!(stdout, Print("  "), Print(cancel))?;
+            }
+            execute!(stdout, Print("\n"))?;
+        }
+
+        let key = match read_key()? {
+            Some(k) => k,
+            None => return Ok(()),
+        };
+        let rows = visible.len() + usize::from(self.cancel_option.is_some());
+        match key.code {
+            KeyCode::Down if rows > 0 => {
+                self.local_state.offset = (self.local_state.offset + 1) % rows;
+            }
+            KeyCode::Up if rows > 0 => {
+                self.local_state.offset = (self.local_state.offset + rows - 1) % rows;
+            }
+            KeyCode::Enter => {
+                if self.local_state.offset >= visible.len() {
+                    self.local_state.is_cancelled = self.cancel_option.is_some();
+                    self.local_state.is_selected = self.cancel_option.is_some();
+                } else {
+                    self.local_state.is_selected = true;
+                }
+            }
+            KeyCode::Esc => {
+                self.local_state.is_cancelled = true;
+                self.local_state.is_selected = true;
+            }
+            KeyCode::Backspace => {
+                self.local_state.filter.pop();
+                self.local_state.offset = 0;
+            }
+            KeyCode::Char(c) if !is_shortcut(key.modifiers) => {
+                self.local_state.filter.push(c);
+                self.local_state.offset = 0;
+            }
+            _ => {}
+        }
+        self.update_current();
+        Ok(())
+    }
+}
+
+impl<'a, T: Clone> WidgetChild<T> for FilterList<'a, T> {
+    fn before_render(&mut self, _: T) -> RenderWidget {
+        RenderWidget::Yes
+    }
+
+    fn after_render(&mut self, global_state: T) -> Action {
+        (self.cb_after)(&mut self.local_state, global_state)
+    }
+}
+
+impl<'a, T: Clone> FilterList<'a, T> {
+    pub fn new(options: Vec<String>) -> Self {
+        let mut list = Self {
+            options,
+            cancel_option: None,
+            text_init: IconAndLabel(ICON_QUESTION, "Choose an option: "),
+            text_final: IconAndLabel(ICON_CHECK, "Option selected: "),
+            cb_after: Box::new(|_, _| Action::Next),
+            local_state: FilterListData {
+                is_selected: false,
+                is_cancelled: false,
+                filter: String::new(),
+                offset: 0,
+                current_option: None,
+                index: None,
+            },
+        };
+        list.update_current();
+        list
+    }
+    pub fn after(&mut self, cb: impl FnMut(&mut FilterListData, T) -> Action + 'static) {
+        self.cb_after = Box::new(cb);
+    }
+    pub fn add_text_init(&mut self, icon: &'a str, label: &'a str) {
+        self.text_init = IconAndLabel(icon, label);
+    }
+    pub fn add_text_final(&mut self, icon: &'a str, label: &'a str) {
+        self.text_final = IconAndLabel(icon, label);
+    }
+    /// Add an option, always shown after the others, to choose none of them.
+    pub fn add_cancel_option(&mut self, label: &'a str) {
+        self.cancel_option = Some(label);
+    }
+
+    /// Options matching the filter, with the indices of the matched characters.
+    fn visible_options(&self) -> Vec<(usize, Vec<usize>)> {
+        if self.local_state.filter.is_empty() {
+            return (0..self.options.len()).map(|i| (i, Vec::new())).collect();
+        }
+        let mut matches: Vec<(i64, usize, Vec<usize>)> = self
+            .options
+            .iter()
+            .enumerate()
+            .filter_map(|(i, option)| {
+                fuzzy_match(&self.local_state.filter, option).map(|m| (m.score, i, m.indices))
+            })
+            .collect();
+        matches.sort_by_key(|m| std::cmp::Reverse(m.0));
+        matches.into_iter().map(|(_, i, m)| (i, m)).collect()
+    }
+
+    fn update_current(&mut self) {
+        let index = self
+            .visible_options()
+            .get(self.local_state.offset)
+            .map(|(i, _)| *i);
+        self.local_state.index = index;
+        self.local_state.current_option = index.map(|i| self.options[i].clone());
+    }
+}
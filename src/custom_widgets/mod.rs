@@ -1,3 +1,13 @@
+mod filter_list;
+mod multi_select;
+mod text_input;
+
+use std::{
+    io::{self, stdout},
+    process,
+};
+
+use chrono::Local;
 use cli_printer::{
     core::{
         interfaces::{Widget, WidgetChild},
@@ -6,21 +16,78 @@ use cli_printer::{
     styles::ICON_QUESTION,
 };
 use crossterm::{
-    event::{self, Event, KeyCode},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     execute,
     style::{Print, Stylize},
+    terminal::{self, LeaveAlternateScreen},
 };
 
-use crate::todo::todo::{Todo, TodoState};
+use crate::{
+    config,
+    todo::todo::{DueState, Priority, Todo, TodoState},
+    utils::fuzzy::fuzzy_match,
+};
 
-type AfterCb<T> = dyn FnMut(&mut CheckListData, T) -> Action;
+pub use self::{
+    filter_list::FilterList,
+    multi_select::MultiSelect,
+    text_input::{EditResult, LineEditor, TextInput},
+};
 
-type BeforeCb<T> = dyn FnMut(&mut CheckListData, T) -> RenderWidget;
+/// Wait for a key press.
+///
+/// The terminal is in raw mode while waiting, so every key arrives without
+/// waiting for Enter. Ctrl+C leaves the program.
+fn read_key() -> io::Result<Option<KeyEvent>> {
+    terminal::enable_raw_mode()?;
+    let event = event::read();
+    terminal::disable_raw_mode()?;
+
+    match event? {
+        Event::Key(k) if k.kind != KeyEventKind::Release => {
+            if k.code == KeyCode::Char('c') && k.modifiers.contains(KeyModifiers::CONTROL) {
+                execute!(stdout(), LeaveAlternateScreen)?;
+                process::exit(130);
+            }
+            Ok(Some(k))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Render `text` with the characters at `indices` highlighted, and the rest
+/// styled with `style`.
+fn highlight(text: &str, indices: &[usize], style: impl Fn(&str) -> String) -> String {
+    let mut result = String::new();
+    let mut segment = String::new();
+    let mut segment_matched = false;
+
+    for (i, c) in text.chars().enumerate() {
+        let matched = indices.contains(&i);
+        if matched != segment_matched && !segment.is_empty() {
+            result.push_str(&style_segment(&segment, segment_matched, &style));
+            segment.clear();
+        }
+        segment_matched = matched;
+        segment.push(c);
+    }
+    result.push_str(&style_segment(&segment, segment_matched, &style));
+    result
+}
+
+fn style_segment(segment: &str, matched: bool, style: &impl Fn(&str) -> String) -> String {
+    if matched {
+        format!("{}", segment.cyan().bold())
+    } else {
+        style(segment)
+    }
+}
+
+type AfterCb<T> = dyn FnMut(&mut CheckListData, T) -> Action;
 
 pub struct CheckList<'a, T> {
     pub label: IconAndLabel<'a>,
     pub local_state: CheckListData,
-    cb_before: Box<BeforeCb<T>>,
     cb_after: Box<AfterCb<T>>,
 }
 
@@ -28,8 +95,13 @@ pub struct CheckListData {
     pub is_selected: bool,
     pub offset: usize,
     pub current_option: Option<String>,
-    pub length: usize,
     pub todos: Vec<Todo>,
+    /// Only the todos matching the filter are shown.
+    pub filter: String,
+    /// Ids of the todos deleted with the Delete key.
+    pub deleted: Vec<u32>,
+    /// Description of the current todo being edited with Ctrl+E.
+    editing: Option<LineEditor>,
 }
 
 impl<'a, T: Clone> Widget for CheckList<'a, T> {
@@ -41,64 +113,109 @@ impl<'a, T: Clone> Widget for CheckList<'a, T> {
             Print("\n"),
         )?;
         if !self.local_state.is_selected {
-            let mut i = 0;
-            for todo in &self.local_state.todos {
-                let text = format!("{} {}", todo.icon.clone(), todo.description.clone());
-                if self.local_state.offset == i {
-                    let text_colored = color_todo(&todo.status, &format!("> {}", text));
-                    execute!(stdout, Print(text_colored),)?;
+            let filter_text = if self.local_state.filter.is_empty() {
+                "type to filter".dark_grey().to_string()
+            } else {
+                self.local_state.filter.clone()
+            };
+            execute!(stdout, Print(format!("{} {}\n", "/".cyan(), filter_text)))?;
+
+            for (row, (index, matched)) in self.visible_todos().iter().enumerate() {
+                let todo = &self.local_state.todos[*index];
+                let prefix = if self.local_state.offset == row {
+                    "> "
                 } else {
-                    let text_colored = color_todo(&todo.status, &format!("  {}", text));
-                    execute!(stdout, Print(text_colored))?;
-                }
+                    "  "
+                };
+                let icon = color_todo(&todo.status, &format!("{}{} ", prefix, todo.icon()));
+                let priority = color_priority(todo.priority, &todo.priority.to_string());
+                let description = match &self.local_state.editing {
+                    Some(editor) if self.local_state.offset == row => editor.render(),
+                    _ => highlight(&todo.description, matched, |s| color_todo(&todo.status, s)),
+                };
+                execute!(
+                    stdout,
+                    Print(format!(
+                        "{}{} {}{}",
+                        icon,
+                        priority,
+                        description,
+                        due_text(todo)
+                    ))
+                )?;
                 execute!(stdout, Print("\n"))?;
-                i += 1;
             }
 
-            match event::read()? {
-                Event::Key(k) => match k.code {
-                    KeyCode::Down => self.next(),
-                    KeyCode::Up => self.prev(),
-                    KeyCode::Left => {
-                        let current_todo = self.get_todo_mut();
-                        if let Some(todo) = current_todo {
-                            let new_icon = match todo.status {
-                                TodoState::Completed => TodoState::NoStarted,
-                                TodoState::NoStarted => TodoState::Completed,
-                            };
-                            todo.change_icon(new_icon)
-                        }
+            let key = match read_key()? {
+                Some(k) => k,
+                None => return Ok(()),
+            };
+            if let Some(editor) = &mut self.local_state.editing {
+                let result = editor.handle_key(&key);
+                if result == EditResult::Confirmed {
+                    let text = editor.text();
+                    if let Some(todo) = self.get_todo_mut().filter(|_| !text.trim().is_empty()) {
+                        todo.set_description(&text);
+                    }
+                }
+                if result != EditResult::Editing {
+                    self.local_state.editing = None;
+                }
+                return Ok(());
+            }
+            match key.code {
+                KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    if let Some(todo) = self.get_todo_mut() {
+                        let editor = LineEditor::new(&todo.description);
+                        self.local_state.editing = Some(editor);
+                    }
+                }
+                KeyCode::Delete => self.delete_current(),
+                KeyCode::Down => self.next(),
+                KeyCode::Up => self.prev(),
+                KeyCode::Left => {
+                    let current_todo = self.get_todo_mut();
+                    if let Some(todo) = current_todo {
+                        let new_icon = todo.status.prev();
+                        todo.change_icon(new_icon)
                     }
-                    KeyCode::Right => {
-                        let current_todo = self.get_todo_mut();
-                        if let Some(todo) = current_todo {
-                            let new_icon = match todo.status {
-                                TodoState::Completed => TodoState::NoStarted,
-                                TodoState::NoStarted => TodoState::Completed,
-                            };
-                            todo.change_icon(new_icon)
-                        }
+                }
+                KeyCode::Right => {
+                    let current_todo = self.get_todo_mut();
+                    if let Some(todo) = current_todo {
+                        let new_icon = todo.status.next();
+                        todo.change_icon(new_icon)
                     }
-                    KeyCode::Enter => self.local_state.is_selected = true,
-                    _ => {}
-                },
+                }
+                KeyCode::Enter => self.local_state.is_selected = true,
+                KeyCode::Backspace => {
+                    self.local_state.filter.pop();
+                    self.local_state.offset = 0;
+                }
+                KeyCode::Char(c) if !text_input::is_shortcut(key.modifiers) => {
+                    self.local_state.filter.push(c);
+                    self.local_state.offset = 0;
+                }
                 _ => {}
             }
             return Ok(());
         }
 
-        let mut i = 0;
         for todo in &self.local_state.todos {
-            let text = format!("{} {}", todo.icon.clone(), todo.description.clone());
-            if self.local_state.offset == i {
-                let text_colored = color_todo(&todo.status, &format!("  {}", text));
-                execute!(stdout, Print(text_colored))?;
-            } else {
-                let text_colored = color_todo(&todo.status, &format!("  {}", text));
-                execute!(stdout, Print(text_colored))?;
-            }
+            let icon = color_todo(&todo.status, &format!("  {} ", todo.icon()));
+            let priority = color_priority(todo.priority, &todo.priority.to_string());
+            let description = color_todo(&todo.status, &todo.description);
+            execute!(
+                stdout,
+                Print(format!(
+                    "{}{} {}{}",
+                    icon,
+                    priority,
+                    description,
+                    due_text(todo)
+                ))
+            )?;
             execute!(stdout, Print("\n"))?;
-            i += 1;
         }
 
         Ok(())
@@ -106,8 +223,8 @@ impl<'a, T: Clone> Widget for CheckList<'a, T> {
 }
 
 impl<'a, T: Clone> WidgetChild<T> for CheckList<'a, T> {
-    fn before_render(&mut self, global_state: T) -> cli_printer::core::utils::RenderWidget {
-        (self.cb_before)(&mut self.local_state, global_state)
+    fn before_render(&mut self, _: T) -> RenderWidget {
+        RenderWidget::Yes
     }
 
     fn after_render(&mut self, global_state: T) -> cli_printer::core::utils::Action {
@@ -117,7 +234,6 @@ impl<'a, T: Clone> WidgetChild<T> for CheckList<'a, T> {
 
 impl<'a, T: Clone> CheckList<'a, T> {
     pub fn new(todos: Vec<Todo>) -> Self {
-        let length = todos.len();
         Self {
             label: IconAndLabel(ICON_QUESTION, "Change the todos: "),
             local_state: CheckListData {
@@ -125,59 +241,121 @@ impl<'a, T: Clone> CheckList<'a, T> {
                 is_selected: false,
                 offset: 0,
                 current_option: None,
-                length,
+                filter: String::new(),
+                deleted: Vec::new(),
+                editing: None,
             },
             cb_after: Box::new(|_, _| Action::Next),
-            cb_before: Box::new(|_, _| RenderWidget::Yes),
         }
     }
     pub fn after(&mut self, cb: impl FnMut(&mut CheckListData, T) -> Action + 'static) {
         self.cb_after = Box::new(cb);
     }
-    pub fn before(&mut self, cb: impl FnMut(&mut CheckListData, T) -> RenderWidget + 'static) {
-        self.cb_before = Box::new(cb);
+    /// Todos matching the filter, with the indices of the matched characters.
+    fn visible_todos(&self) -> Vec<(usize, Vec<usize>)> {
+        let todos = &self.local_state.todos;
+        if self.local_state.filter.is_empty() {
+            return (0..todos.len()).map(|i| (i, Vec::new())).collect();
+        }
+        todos
+            .iter()
+            .enumerate()
+            .filter_map(|(i, todo)| {
+                fuzzy_match(&self.local_state.filter, &todo.description).map(|m| (i, m.indices))
+            })
+            .collect()
+    }
+    fn move_to(&mut self, new_offset: usize) {
+        let index = self.visible_todos().get(new_offset).map(|(i, _)| *i);
+        self.local_state.current_option =
+            index.map(|i| self.local_state.todos[i].description.to_owned());
+        self.local_state.offset = new_offset;
     }
     pub fn prev(&mut self) {
+        let length = self.visible_todos().len();
+        if length == 0 {
+            return;
+        }
         let new_offset = if self.local_state.offset == 0 {
-            self.local_state.todos.len() - 1
+            length - 1
         } else {
             self.local_state.offset - 1
         };
-
-        self.local_state.current_option = self
-            .local_state
-            .todos
-            .get(new_offset)
-            .map(|s| s.description.to_owned());
-        self.local_state.offset = new_offset;
+        self.move_to(new_offset);
     }
     pub fn next(&mut self) {
-        let new_offset = if self.local_state.offset >= self.local_state.todos.len() - 1 {
+        let length = self.visible_todos().len();
+        if length == 0 {
+            return;
+        }
+        let new_offset = if self.local_state.offset >= length - 1 {
             0
         } else {
             self.local_state.offset + 1
         };
+        self.move_to(new_offset);
+    }
 
-        self.local_state.current_option = self
-            .local_state
-            .todos
-            .get(new_offset)
-            .map(|s| s.description.to_owned());
-        self.local_state.offset = new_offset;
+    /// Remove the current todo from the list, keeping its id in `deleted`.
+    fn delete_current(&mut self) {
+        let index = match self.visible_todos().get(self.local_state.offset) {
+            Some((index, _)) => *index,
+            None => return,
+        };
+        let todo = self.local_state.todos.remove(index);
+        self.local_state.deleted.push(todo.id);
+
+        let length = self.visible_todos().len();
+        self.move_to(self.local_state.offset.min(length.saturating_sub(1)));
     }
 
     pub fn get_todo_mut(&mut self) -> Option<&mut Todo> {
-        self.local_state.todos.get_mut(self.local_state.offset)
+        let index = self.visible_todos().get(self.local_state.offset)?.0;
+        self.local_state.todos.get_mut(index)
+    }
+}
+
+/// Style `text` with the colour of a due date in `state`.
+pub fn color_due(state: Option<DueState>, text: &str) -> String {
+    match state.and_then(|s| s.color()) {
+        Some(color) => format!("{}", text.with(color)),
+        None => text.to_string(),
+    }
+}
+
+/// Due date shown after the description of a todo, empty without one.
+fn due_text(todo: &Todo) -> String {
+    let due = match todo.due {
+        Some(due) => due,
+        None => return String::new(),
+    };
+    let state = todo.due_state(Local::now().date_naive());
+    let text = match state {
+        Some(DueState::Overdue) => format!("(overdue {})", due),
+        Some(DueState::Today) => "(due today)".to_string(),
+        _ => format!("(due {})", due),
+    };
+    match state.and_then(|s| s.color()) {
+        Some(color) => format!(" {}", text.with(color)),
+        None => format!(" {}", text.dark_grey()),
+    }
+}
+
+/// Style `text` with the colour of `priority`.
+pub fn color_priority(priority: Priority, text: &str) -> String {
+    match priority.color() {
+        Some(color) => format!("{}", text.with(color)),
+        None => text.to_string(),
     }
 }
 
 fn color_todo(todo_status: &TodoState, text: &str) -> String {
+    let styled = match config::get().color(todo_status) {
+        Some(color) => text.with(color),
+        None => text.stylize(),
+    };
     match todo_status {
-        TodoState::Completed => {
-            format!("{}", text.green())
-        }
-        TodoState::NoStarted => {
-            format!("{}", text)
-        }
+        TodoState::Cancelled => format!("{}", styled.crossed_out()),
+        _ => format!("{}", styled),
     }
 }
@@ -0,0 +1,144 @@
+use cli_printer::{
+    core::{
+        interfaces::{Widget, WidgetChild},
+        utils::{Action, IconAndLabel, RenderWidget},
+    },
+    styles::{ICON_CHECK, ICON_QUESTION},
+};
+use crossterm::{
+    event::KeyCode,
+    execute,
+    style::{Print, Stylize},
+};
+
+use super::read_key;
+
+type AfterCb<T> = dyn FnMut(&mut MultiSelectData, T) -> Action;
+
+/// List to choose any number of options, all of them checked at first.
+pub struct MultiSelect<'a, T> {
+    options: Vec<String>,
+    text_init: IconAndLabel<'a>,
+    text_final: IconAndLabel<'a>,
+    cb_after: Box<AfterCb<T>>,
+    local_state: MultiSelectData,
+}
+
+pub struct MultiSelectData {
+    pub is_selected: bool,
+    /// Esc was pressed, nothing must be done with the options.
+    pub is_cancelled: bool,
+    pub offset: usize,
+    /// Whether each option is checked, in the order given to `new`.
+    pub checked: Vec<bool>,
+}
+
+impl<'a, T: Clone> Widget for MultiSelect<'a, T> {
+    fn render(&mut self, stdout: &mut std::io::Stdout) -> std::io::Result<()> {
+        let checked_count = self.local_state.checked.iter().filter(|c| **c).count();
+        if self.local_state.is_selected {
+            let text_selected = match self.local_state.is_cancelled {
+                true => "Cancelled".to_string(),
+                false => format!("{} of {}", checked_count, self.options.len()),
+            };
+            execute!(
+                stdout,
+                Print(self.text_final.0.green()),
+                Print(self.text_final.1),
+                Print(text_selected.dark_grey()),
+            )?;
+            return Ok(());
+        }
+
+        execute!(
+            stdout,
+            Print(self.text_init.0.cyan()),
+            Print(self.text_init.1),
+            Print("\n"),
+            Print(
+                "Space: Check  a: All/None  Enter: Confirm  Esc: Cancel\n"
+                    .dark_grey()
+                    .to_string()
+            ),
+        )?;
+        for (row, option) in self.options.iter().enumerate() {
+            let prefix = if row == self.local_state.offset {
+                "> ".cyan().to_string()
+            } else {
+                "  ".to_string()
+            };
+            let check = if self.local_state.checked[row] {
+                "[x]".green().to_string()
+            } else {
+                "[ ]".to_string()
+            };
+            execute!(stdout, Print(format!("{}{} {}\n", prefix, check, option)))?;
+        }
+
+        let key = match read_key()? {
+            Some(k) => k,
+            None => return Ok(()),
+        };
+        let rows = self.options.len();
+        match key.code {
+            KeyCode::Down if rows > 0 => {
+                self.local_state.offset = (self.local_state.offset + 1) % rows;
+            }
+            KeyCode::Up if rows > 0 => {
+                self.local_state.offset = (self.local_state.offset + rows - 1) % rows;
+            }
+            KeyCode::Char(' ') if rows > 0 => {
+                let checked = &mut self.local_state.checked[self.local_state.offset];
+                *checked = !*checked;
+            }
+            KeyCode::Char('a') => {
+                let all_checked = checked_count == rows;
+                self.local_state.checked.fill(!all_checked);
+            }
+            KeyCode::Enter => self.local_state.is_selected = true,
+            KeyCode::Esc => {
+                self.local_state.is_cancelled = true;
+                self.local_state.is_selected = true;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: Clone> WidgetChild<T> for MultiSelect<'a, T> {
+    fn before_render(&mut self, _: T) -> RenderWidget {
+        RenderWidget::Yes
+    }
+
+    fn after_render(&mut self, global_state: T) -> Action {
+        (self.cb_after)(&mut self.local_state, global_state)
+    }
+}
+
+impl<'a, T: Clone> MultiSelect<'a, T> {
+    pub fn new(options: Vec<String>) -> Self {
+        let checked = vec![true; options.len()];
+        Self {
+            options,
+            text_init: IconAndLabel(ICON_QUESTION, "Choose the options: "),
+            text_final: IconAndLabel(ICON_CHECK, "Options chosen: "),
+            cb_after: Box::new(|_, _| Action::Next),
+            local_state: MultiSelectData {
+                is_selected: false,
+                is_cancelled: false,
+                offset: 0,
+                checked,
+            },
+        }
+    }
+    pub fn after(&mut self, cb: impl FnMut(&mut MultiSelectData, T) -> Action + 'static) {
+        self.cb_after = Box::new(cb);
+    }
+    pub fn add_text_init(&mut self, icon: &'a str, label: &'a str) {
+        self.text_init = IconAndLabel(icon, label);
+    }
+    pub fn add_text_final(&mut self, icon: &'a str, label: &'a str) {
+        self.text_final = IconAndLabel(icon, label);
+    }
+}
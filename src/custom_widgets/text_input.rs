@@ -0,0 +1,237 @@
+use cli_printer::{
+    core::{
+        interfaces::{Widget, WidgetChild},
+        utils::{Action, IconAndLabel, RenderWidget},
+    },
+    styles::{ICON_CHECK, ICON_QUESTION},
+};
+use crossterm::{
+    event::{KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    style::{Print, Stylize},
+};
+
+use super::read_key;
+
+/// Result of a key given to a `LineEditor`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum EditResult {
+    Editing,
+    /// Enter was pressed.
+    Confirmed,
+    /// Esc was pressed, the text must be discarded.
+    Cancelled,
+}
+
+/// Text edited on a single line, with a cursor that can be moved.
+pub struct LineEditor {
+    chars: Vec<char>,
+    /// Position of the cursor in `chars`, it can be after the last one.
+    cursor: usize,
+}
+
+impl LineEditor {
+    /// Start editing `text`, with the cursor at the end.
+    pub fn new(text: &str) -> Self {
+        let chars: Vec<char> = text.chars().collect();
+        let cursor = chars.len();
+        Self { chars, cursor }
+    }
+
+    pub fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    pub fn handle_key(&mut self, key: &KeyEvent) -> EditResult {
+        match key.code {
+            KeyCode::Enter => return EditResult::Confirmed,
+            KeyCode::Esc => return EditResult::Cancelled,
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.chars.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.chars.len(),
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.chars.remove(self.cursor);
+            }
+            KeyCode::Delete if self.cursor < self.chars.len() => {
+                self.chars.remove(self.cursor);
+            }
+            KeyCode::Char(_) if is_shortcut(key.modifiers) => {}
+            KeyCode::Char(c) => {
+                self.chars.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            _ => {}
+        }
+        EditResult::Editing
+    }
+
+    /// The text with the character under the cursor in reverse video.
+    pub fn render(&self) -> String {
+        let before: String = self.chars[..self.cursor].iter().collect();
+        let (cursor, after) = match self.chars.get(self.cursor) {
+            Some(c) => (
+                c.to_string(),
+                self.chars[self.cursor + 1..].iter().collect(),
+            ),
+            None => (" ".to_string(), String::new()),
+        };
+        format!("{}{}{}", before, cursor.reverse(), after)
+    }
+}
+
+/// Whether a character typed with `modifiers` is a shortcut like Ctrl+E, and not text.
+pub fn is_shortcut(modifiers: KeyModifiers) -> bool {
+    // AltGr is sent as Ctrl+Alt on Windows, and types text
+    let control_alt = KeyModifiers::CONTROL | KeyModifiers::ALT;
+    modifiers.intersects(control_alt) && !modifiers.contains(control_alt)
+}
+
+type AfterCb<T> = dyn FnMut(&mut TextInputData, T) -> Action;
+
+/// Input to type a line of text, which can start with some text already written.
+pub struct TextInput<'a, T> {
+    editor: LineEditor,
+    text_init: IconAndLabel<'a>,
+    text_final: IconAndLabel<'a>,
+    cb_after: Box<AfterCb<T>>,
+    local_state: TextInputData,
+}
+
+pub struct TextInputData {
+    pub is_selected: bool,
+    /// Esc was pressed, the text must not be used.
+    pub is_cancelled: bool,
+    /// Text typed, set once it is confirmed.
+    pub text: String,
+}
+
+impl<'a, T: Clone> Widget for TextInput<'a, T> {
+    fn render(&mut self, stdout: &mut std::io::Stdout) -> std::io::Result<()> {
+        if self.local_state.is_selected {
+            let text_selected = match self.local_state.is_cancelled {
+                true => "Cancelled".to_string(),
+                false => self.local_state.text.clone(),
+            };
+            execute!(
+                stdout,
+                Print(self.text_final.0.green()),
+                Print(self.text_final.1),
+                Print(text_selected.dark_grey()),
+            )?;
+            return Ok(());
+        }
+
+        execute!(
+            stdout,
+            Print(self.text_init.0.cyan()),
+            Print(self.text_init.1),
+            Print(self.editor.render()),
+            Print("\n"),
+            Print("Enter: Confirm  Esc: Cancel\n".dark_grey().to_string()),
+        )?;
+
+        let key = match read_key()? {
+            Some(k) => k,
+            None => return Ok(()),
+        };
+        match self.editor.handle_key(&key) {
+            EditResult::Editing => {}
+            EditResult::Confirmed => {
+                self.local_state.text = self.editor.text();
+                self.local_state.is_selected = true;
+            }
+            EditResult::Cancelled => {
+                self.local_state.is_cancelled = true;
+                self.local_state.is_selected = true;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: Clone> WidgetChild<T> for TextInput<'a, T> {
+    fn before_render(&mut self, _global_state: T) -> RenderWidget {
+        RenderWidget::Yes
+    }
+
+    fn after_render(&mut self, global_state: T) -> Action {
+        (self.cb_after)(&mut self.local_state, global_state)
+    }
+}
+
+impl<'a, T: Clone> TextInput<'a, T> {
+    /// Input starting with `text`, the cursor at its end.
+    pub fn new(text: &str) -> Self {
+        Self {
+            editor: LineEditor::new(text),
+            text_init: IconAndLabel(ICON_QUESTION, "Type the text: "),
+            text_final: IconAndLabel(ICON_CHECK, "Text: "),
+            cb_after: Box::new(|_, _| Action::Next),
+            local_state: TextInputData {
+                is_selected: false,
+                is_cancelled: false,
+                text: String::new(),
+            },
+        }
+    }
+    pub fn after(&mut self, cb: impl FnMut(&mut TextInputData, T) -> Action + 'static) {
+        self.cb_after = Box::new(cb);
+    }
+    pub fn add_text_init(&mut self, icon: &'a str, label: &'a str) {
+        self.text_init = IconAndLabel(icon, label);
+    }
+    pub fn add_text_final(&mut self, icon: &'a str, label: &'a str) {
+        self.text_final = IconAndLabel(icon, label);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_keys(editor: &mut LineEditor, keys: &[(KeyCode, KeyModifiers)]) {
+        for (code, modifiers) in keys {
+            editor.handle_key(&KeyEvent::new(*code, *modifiers));
+        }
+    }
+
+    #[test]
+    fn keys_edit_at_the_cursor() {
+        let mut editor = LineEditor::new("héllo");
+        let none = KeyModifiers::NONE;
+        type_keys(
+            &mut editor,
+            &[
+                (KeyCode::Left, none),
+                (KeyCode::Backspace, none),
+                (KeyCode::Char('L'), KeyModifiers::SHIFT),
+                (KeyCode::Home, none),
+                (KeyCode::Delete, none),
+                (KeyCode::End, none),
+                (KeyCode::Char('!'), none),
+            ],
+        );
+        assert_eq!(editor.text(), "élLo!");
+    }
+
+    #[test]
+    fn shortcuts_are_not_typed() {
+        let mut editor = LineEditor::new("a");
+        type_keys(
+            &mut editor,
+            &[
+                (KeyCode::Char('e'), KeyModifiers::CONTROL),
+                (KeyCode::Char('b'), KeyModifiers::ALT),
+                (
+                    KeyCode::Char('@'),
+                    KeyModifiers::CONTROL | KeyModifiers::ALT,
+                ),
+            ],
+        );
+        assert_eq!(editor.text(), "a@");
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(editor.handle_key(&enter), EditResult::Confirmed);
+    }
+}
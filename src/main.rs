@@ -1,12 +1,19 @@
+use std::process;
+
 use cli::App;
 
 mod cli;
+mod config;
 mod constants;
 mod custom_widgets;
+mod shell;
 mod tidy;
 mod todo;
 mod utils;
 
 fn main() {
-    let _ = App::run();
+    if let Err(e) = App::run() {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,63 @@
+use std::io::{self, stdout, Write};
+
+use clap::ValueEnum;
+
+/// Shells supported by `wflow init`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Function for bash and zsh.
+///
+/// A child process cannot change the directory of the shell, so the
+/// function runs `tidy cd` and moves to the path it writes. The path goes
+/// through a temporary file because the picker needs the terminal on stdout.
+const POSIX_FUNCTION: &str = r#"wflow() {
+    if [ "$1" = "tidy" ] && [ "$2" = "cd" ]; then
+        shift 2
+        local wflow_out wflow_status
+        wflow_out="$(mktemp)" || return
+        command wflow tidy cd --out "$wflow_out" "$@"
+        wflow_status=$?
+        if [ "$wflow_status" -eq 0 ] && [ -s "$wflow_out" ]; then
+            cd -- "$(cat "$wflow_out")" || wflow_status=$?
+        fi
+        rm -f "$wflow_out"
+        return "$wflow_status"
+    fi
+    command wflow "$@"
+}
+"#;
+
+const FISH_FUNCTION: &str = r#"function wflow
+    if test (count $argv) -ge 2; and test "$argv[1]" = tidy; and test "$argv[2]" = cd
+        set -l wflow_out (mktemp); or return
+        command wflow tidy cd --out $wflow_out $argv[3..-1]
+        set -l wflow_status $status
+        if test $wflow_status -eq 0; and test -s $wflow_out
+            cd (cat $wflow_out); or set wflow_status $status
+        end
+        rm -f $wflow_out
+        return $wflow_status
+    end
+    command wflow $argv
+end
+"#;
+
+pub struct InitProgram;
+
+impl InitProgram {
+    /// Print the function to evaluate in the startup file of `shell`.
+    pub fn run(shell: Shell) -> io::Result<()> {
+        let function = match shell {
+            Shell::Bash | Shell::Zsh => POSIX_FUNCTION,
+            Shell::Fish => FISH_FUNCTION,
+        };
+        let mut stdout = stdout();
+        stdout.write_all(function.as_bytes())?;
+        stdout.flush()
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+};
+
+use super::{canonicalize_path, projects_db::Project};
+
+/// Something wrong with a saved project.
+pub enum Problem {
+    /// The folder does not exist anymore.
+    Missing,
+    NotADirectory,
+    /// Another entry, saved before, points to the same folder: its index
+    /// and its path.
+    Duplicate(usize, String),
+    /// The saved path is a symlink, has a trailing slash or is not absolute.
+    Moved(PathBuf),
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Missing => write!(f, "The folder does not exist"),
+            Problem::NotADirectory => write!(f, "The path is not a folder"),
+            Problem::Duplicate(_, path) => write!(f, "Duplicate of {}", path),
+            Problem::Moved(path) => write!(f, "Resolves to {}", path.display()),
+        }
+    }
+}
+
+/// Path used to compare the projects, the real one when it exists.
+pub fn resolved_path(path: &str) -> PathBuf {
+    canonicalize_path(path).unwrap_or_else(|_| {
+        let trimmed = path.trim_end_matches(['/', '\\']);
+        PathBuf::from(if trimmed.is_empty() { path } else { trimmed })
+    })
+}
+
+/// Find the problems of `projects`, at most one for each project, with its index.
+pub fn check_projects(projects: &[Project]) -> Vec<(usize, Problem)> {
+    let mut seen: Vec<PathBuf> = Vec::new();
+    let mut problems = Vec::new();
+
+    for (index, project) in projects.iter().enumerate() {
+        let path = Path::new(&project.path);
+        let resolved = resolved_path(&project.path);
+
+        let problem = if let Some(first) = seen.iter().position(|p| *p == resolved) {
+            Some(Problem::Duplicate(first, projects[first].path.clone()))
+        } else if !path.exists() {
+            Some(Problem::Missing)
+        } else if !path.is_dir() {
+            Some(Problem::NotADirectory)
+        } else if resolved != path {
+            Some(Problem::Moved(resolved.clone()))
+        } else {
+            None
+        };
+
+        seen.push(resolved);
+        if let Some(problem) = problem {
+            problems.push((index, problem));
+        }
+    }
+    problems
+}
+
+/// Fix the `problems` found in `projects` by `check_projects`.
+///
+/// The moved projects get their real path, the duplicates are merged into
+/// the first entry of their folder and the other ones are removed. Returns
+/// the projects left, and the old and new path of each entry that changed.
+pub fn fix_projects(
+    projects: Vec<Project>,
+    problems: &[(usize, Problem)],
+) -> (Vec<Project>, Vec<(String, String)>) {
+    let mut projects: Vec<Option<Project>> = projects.into_iter().map(Some).collect();
+    let mut renamed = Vec::new();
+
+    // The moved ones first, so the duplicates follow the new path
+    for (index, problem) in problems {
+        if let (Problem::Moved(new_path), Some(project)) = (problem, &mut projects[*index]) {
+            let new_path = new_path.display().to_string();
+            renamed.push((project.path.clone(), new_path.clone()));
+            project.path = new_path;
+        }
+    }
+    for (index, problem) in problems {
+        match problem {
+            Problem::Duplicate(first, _) => {
+                let duplicate = projects[*index].take();
+                if let (Some(duplicate), Some(kept)) = (duplicate, &mut projects[*first]) {
+                    renamed.push((duplicate.path.clone(), kept.path.clone()));
+                    kept.merge(duplicate);
+                }
+            }
+            Problem::Missing | Problem::NotADirectory => projects[*index] = None,
+            Problem::Moved(_) => {}
+        }
+    }
+    (projects.into_iter().flatten().collect(), renamed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicates_are_merged_into_the_first_entry() {
+        let mut first = Project::new("/code/app");
+        first.aliases = vec!["a".to_string()];
+        first.open_count = 2;
+        let mut duplicate = Project::new("/code/app");
+        duplicate.aliases = vec!["a".to_string(), "b".to_string()];
+        duplicate.tags = vec!["work".to_string()];
+        duplicate.open_count = 3;
+        let problems = vec![(1, Problem::Duplicate(0, "/code/app".to_string()))];
+
+        let (kept, renamed) = fix_projects(vec![first, duplicate], &problems);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].path, "/code/app");
+        assert_eq!(kept[0].aliases, vec!["a", "b"]);
+        assert_eq!(kept[0].tags, vec!["work"]);
+        assert_eq!(kept[0].open_count, 5);
+        assert_eq!(
+            renamed,
+            vec![("/code/app".to_string(), "/code/app".to_string())]
+        );
+    }
+
+    #[test]
+    fn duplicates_follow_the_new_path_of_a_moved_entry() {
+        let projects = vec![
+            Project::new("/code/link"),
+            Project::new("/code/gone"),
+            Project::new("/code/real"),
+        ];
+        let problems = vec![
+            (0, Problem::Moved(PathBuf::from("/code/real"))),
+            (1, Problem::Missing),
+            (2, Problem::Duplicate(0, "/code/link".to_string())),
+        ];
+
+        let (kept, renamed) = fix_projects(projects, &problems);
+        let paths: Vec<&str> = kept.iter().map(|p| p.path.as_str()).collect();
+        assert_eq!(paths, vec!["/code/real"]);
+        assert_eq!(
+            renamed,
+            vec![
+                ("/code/link".to_string(), "/code/real".to_string()),
+                ("/code/real".to_string(), "/code/real".to_string()),
+            ]
+        );
+    }
+}
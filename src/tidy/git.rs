@@ -0,0 +1,129 @@
+use std::{path::Path, process::Command};
+
+use chrono::{DateTime, Utc};
+
+/// State of the git repository of a project.
+pub struct GitStatus {
+    /// `None` when the HEAD is detached.
+    pub branch: Option<String>,
+    /// Commits ahead and behind the upstream, `None` without upstream.
+    pub ahead_behind: Option<(u32, u32)>,
+    /// Files changed, staged or untracked.
+    pub changes: usize,
+    /// Commits of the local branches that are on no remote.
+    pub unpushed: usize,
+    /// Date of the last commit, `None` in a repository without commits.
+    pub last_commit: Option<DateTime<Utc>>,
+}
+
+impl GitStatus {
+    /// Whether removing the repository would lose work.
+    pub fn has_unsaved_work(&self) -> bool {
+        self.changes > 0 || self.unpushed > 0
+    }
+}
+
+fn git(path: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+/// Parse the output of `git status --porcelain=v2 --branch`.
+///
+/// The unpushed commits and the last commit are not in it, they stay empty.
+fn parse_porcelain(out: &str) -> GitStatus {
+    let mut status = GitStatus {
+        branch: None,
+        ahead_behind: None,
+        changes: 0,
+        unpushed: 0,
+        last_commit: None,
+    };
+    for line in out.lines() {
+        if let Some(head) = line.strip_prefix("# branch.head ") {
+            status.branch = Some(head.to_string()).filter(|h| h != "(detached)");
+        } else if let Some(ab) = line.strip_prefix("# branch.ab ") {
+            let mut counts = ab
+                .split_whitespace()
+                .map(|c| c.trim_start_matches(['+', '-']).parse().unwrap_or(0));
+            status.ahead_behind = Some((counts.next().unwrap_or(0), counts.next().unwrap_or(0)));
+        } else if !line.starts_with('#') {
+            status.changes += 1;
+        }
+    }
+    status
+}
+
+/// Get the status of the repository at `path`.
+///
+/// Returns `None` when the folder is not a git repository or git is not installed.
+pub fn status(path: &Path) -> Option<GitStatus> {
+    if !path.join(".git").exists() {
+        return None;
+    }
+    let porcelain = git(path, &["status", "--porcelain=v2", "--branch"])?;
+    let mut status = parse_porcelain(&porcelain);
+
+    status.unpushed = git(
+        path,
+        &["rev-list", "--count", "--branches", "--not", "--remotes"],
+    )
+    .and_then(|count| count.trim().parse().ok())
+    .unwrap_or(0);
+    status.last_commit = git(path, &["log", "-1", "--format=%ct"])
+        .and_then(|time| time.trim().parse().ok())
+        .and_then(|seconds| DateTime::from_timestamp(seconds, 0));
+    Some(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn branch_and_upstream_are_read() {
+        let status = parse_porcelain(
+            "# branch.oid 1a2b3c\n\
+             # branch.head main\n\
+             # branch.upstream origin/main\n\
+             # branch.ab +2 -5\n",
+        );
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(status.ahead_behind, Some((2, 5)));
+        assert_eq!(status.changes, 0);
+        assert!(!status.has_unsaved_work());
+    }
+
+    #[test]
+    fn detached_head_and_no_upstream() {
+        let status = parse_porcelain("# branch.oid 1a2b3c\n# branch.head (detached)\n");
+        assert_eq!(status.branch, None);
+        assert_eq!(status.ahead_behind, None);
+
+        let status = parse_porcelain("# branch.oid (initial)\n# branch.head feature/x\n");
+        assert_eq!(status.branch.as_deref(), Some("feature/x"));
+        assert_eq!(status.ahead_behind, None);
+    }
+
+    #[test]
+    fn changed_and_untracked_files_are_counted() {
+        let status = parse_porcelain(
+            "# branch.head main\n\
+             1 .M N... 100644 100644 100644 1a2b 1a2b src/main.rs\n\
+             2 R. N... 100644 100644 100644 1a2b 1a2b R100 new.rs\told.rs\n\
+             u UU N... 100644 100644 100644 100644 1a 2b 3c conflict.rs\n\
+             ? notes.txt\n\
+             ? my file.txt\n",
+        );
+        assert_eq!(status.changes, 5);
+        assert!(status.has_unsaved_work());
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    env, io,
+    process::{Command, ExitStatus},
+};
+
+use crate::{config, utils::split_command};
+
+use super::projects_db::Project;
+
+/// Placeholder replaced by the project path in an editor command.
+const PATH_PLACEHOLDER: &str = "{path}";
+
+const DEFAULT_EDITOR: &str = "code";
+
+/// Get the command used to open `project`.
+///
+/// The first one set wins: the `--editor` flag, the editor saved with the
+/// project, `tidy.editor` in the config, `$VISUAL`, `$EDITOR` and VS Code.
+pub fn resolve_editor(editor_flag: Option<&str>, project: &Project) -> String {
+    let from_env = |name: &str| env::var(name).ok().filter(|e| !e.trim().is_empty());
+
+    editor_flag
+        .map(|e| e.to_string())
+        .or_else(|| project.editor.clone())
+        .or_else(|| config::get().editor().map(|e| e.to_string()))
+        .or_else(|| from_env("VISUAL"))
+        .or_else(|| from_env("EDITOR"))
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string())
+}
+
+/// Build the arguments to run `editor` on `path`.
+///
+/// `{path}` is replaced by the path, otherwise the path is added at the end.
+fn editor_args(editor: &str, path: &str) -> Vec<String> {
+    let mut args = split_command(editor);
+    if args.iter().any(|a| a.contains(PATH_PLACEHOLDER)) {
+        for arg in &mut args {
+            *arg = arg.replace(PATH_PLACEHOLDER, path);
+        }
+    } else {
+        args.push(path.to_string());
+    }
+    args
+}
+
+/// Open `path` with `editor` and wait until it exits, so terminal editors
+/// keep the terminal while they run.
+pub fn open_with_editor(editor: &str, path: &str) -> io::Result<ExitStatus> {
+    let args = editor_args(editor, path);
+    let (program, rest) = match args.split_first() {
+        Some(parts) => parts,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The editor command is empty",
+            ))
+        }
+    };
+
+    // `cmd` resolves the `.cmd` and `.bat` launchers, like the one of VS Code
+    let mut command = if cfg!(target_os = "windows") {
+        let mut c = Command::new("cmd");
+        c.arg("/C").arg(program);
+        c
+    } else {
+        Command::new(program)
+    };
+
+    let status = command.args(rest).status().map_err(|e| {
+        if e.kind() == io::ErrorKind::NotFound {
+            io::Error::new(
+                e.kind(),
+                format!(
+                    "Cannot find the editor '{}', change it with `wflow config set tidy.editor <command>`",
+                    program
+                ),
+            )
+        } else {
+            e
+        }
+    })?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "'{}' exited with {}",
+            editor, status
+        )));
+    }
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_path_replaces_the_placeholder_or_goes_last() {
+        assert_eq!(
+            editor_args("idea --path={path}", "/a b"),
+            vec!["idea", "--path=/a b"]
+        );
+        assert_eq!(editor_args("code -n", "/a b"), vec!["code", "-n", "/a b"]);
+    }
+}
@@ -1,14 +1,20 @@
-mod projects_db;
+mod doctor;
+mod git;
+mod launcher;
+pub mod projects_db;
+mod scan;
+mod template;
+mod trash;
 
 use std::{
     cell::RefCell,
     env, fs,
     io::{self, stdout, Stdout},
     path::{Path, PathBuf},
-    process::{Child, Command},
     rc::Rc,
 };
 
+use chrono::{DateTime, Local, Utc};
 use cli_printer::{
     core::{
         interfaces::WidgetRoot,
@@ -16,22 +22,106 @@ use cli_printer::{
         view,
     },
     styles::{ICON_CHECK, ICON_QUESTION},
-    widgets::{self, Input, ListSelected, TextBlock},
+    widgets::{self, Input, ListSelected},
 };
 use crossterm::{
     execute,
     style::{Print, Stylize},
 };
+use serde::Serialize;
 
-use crate::cli::TidyCommands;
+use crate::{
+    cli::{EditProjectArgs, TidyCommands},
+    config,
+    custom_widgets::{FilterList, MultiSelect},
+    todo::{todo_db, todo_utils::table},
+    utils::{
+        self,
+        output::{self, OutputFormat, Record},
+    },
+};
+
+use self::projects_db::{Project, ProjectSort};
+
+/// A project in the `--format` output of `tidy list --git`.
+#[derive(Serialize)]
+struct GitRecord<'a> {
+    name: &'a str,
+    path: &'a str,
+    is_repository: bool,
+    branch: Option<String>,
+    ahead: Option<u32>,
+    behind: Option<u32>,
+    changes: Option<usize>,
+    last_commit: Option<DateTime<Utc>>,
+}
+
+impl Record for GitRecord<'_> {
+    const FIELDS: &'static [&'static str] = &[
+        "name",
+        "path",
+        "is_repository",
+        "branch",
+        "ahead",
+        "behind",
+        "changes",
+        "last_commit",
+    ];
+
+    fn values(&self) -> Vec<String> {
+        let text = |value: Option<String>| value.unwrap_or_default();
+        vec![
+            self.name.to_string(),
+            self.path.to_string(),
+            self.is_repository.to_string(),
+            text(self.branch.clone()),
+            text(self.ahead.map(|n| n.to_string())),
+            text(self.behind.map(|n| n.to_string())),
+            text(self.changes.map(|n| n.to_string())),
+            text(self.last_commit.map(|d| d.to_rfc3339())),
+        ]
+    }
+}
+
+/// A project in the `--format` output of `tidy list`.
+#[derive(Serialize)]
+struct ProjectRecord<'a> {
+    name: &'a str,
+    path: &'a str,
+    aliases: &'a [String],
+    tags: &'a [String],
+    description: Option<&'a str>,
+    open_todos: usize,
+}
+
+impl Record for ProjectRecord<'_> {
+    const FIELDS: &'static [&'static str] = &[
+        "name",
+        "path",
+        "aliases",
+        "tags",
+        "description",
+        "open_todos",
+    ];
+
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.name.to_string(),
+            self.path.to_string(),
+            self.aliases.join(","),
+            self.tags.join(","),
+            self.description.unwrap_or_default().to_string(),
+            self.open_todos.to_string(),
+        ]
+    }
+}
 
 fn canonicalize_path<P: AsRef<Path>>(path: P) -> io::Result<PathBuf> {
     const VERBATIM_PREFIX: &str = r#"\\?\"#;
     let p = path.as_ref().canonicalize()?.display().to_string();
-    let slice_path = if p.starts_with(VERBATIM_PREFIX) {
-        p[VERBATIM_PREFIX.len()..].to_string()
-    } else {
-        p
+    let slice_path = match p.strip_prefix(VERBATIM_PREFIX) {
+        Some(stripped) => stripped.to_string(),
+        None => p,
     };
     let mut new_path = PathBuf::new();
     new_path.push(slice_path);
@@ -41,36 +131,55 @@ fn canonicalize_path<P: AsRef<Path>>(path: P) -> io::Result<PathBuf> {
 pub struct TidyProgram;
 
 impl TidyProgram {
-    pub fn run(tidy_command: &TidyCommands) -> io::Result<()> {
+    pub fn run(tidy_command: &TidyCommands, format: Option<OutputFormat>) -> io::Result<()> {
         let mut stdout = stdout();
 
         match tidy_command {
-            TidyCommands::Open => {
-                return open_project(&mut stdout);
-            }
-            TidyCommands::Add { path } => {
-                return add_project(&mut stdout, path);
+            TidyCommands::Open {
+                query,
+                editor,
+                sort,
+            } => open_project(&mut stdout, query.as_deref(), editor.as_deref(), *sort),
+            TidyCommands::Add { path, editor } => add_project(&mut stdout, path, editor),
+            TidyCommands::New {
+                name,
+                parent,
+                template,
+            } => new_project(
+                &mut stdout,
+                name.as_deref(),
+                parent.as_deref(),
+                template.as_deref(),
+            ),
+            TidyCommands::Remove => remove_project(&mut stdout),
+            TidyCommands::Restore => restore_project(&mut stdout),
+            TidyCommands::List { tag, sort, git } => {
+                display_projects_list(&mut stdout, tag.as_deref(), *sort, *git, format)
             }
-            TidyCommands::New => {
-                return new_project(&mut stdout);
+            TidyCommands::Edit(args) => edit_project(&mut stdout, args),
+            TidyCommands::Scan { dir, depth, yes } => {
+                scan_projects(&mut stdout, dir.as_deref(), *depth, *yes)
             }
-            TidyCommands::Remove => {
-                return remove_project(&mut stdout);
-            }
-            TidyCommands::List => {
-                return display_projects_list(&mut stdout);
+            TidyCommands::Doctor => doctor_projects(&mut stdout),
+            TidyCommands::Prune { yes } => prune_projects(&mut stdout, *yes),
+            TidyCommands::Cd { query, out } => {
+                print_project_path(&mut stdout, query.as_deref(), out.as_deref())
             }
         }
     }
 }
 
-fn add_project(stdout: &mut Stdout, path: &PathBuf) -> io::Result<()> {
+fn add_project(stdout: &mut Stdout, path: &PathBuf, editor: &Option<String>) -> io::Result<()> {
     let path_to_save = canonicalize_path(path)?;
     if !path_to_save.is_dir() {
         execute!(stdout, Print("The path is not the path of a folder"))?;
         return Ok(());
     }
     projects_db::append_to_first_project(&path_to_save)?;
+    if let Some(editor) = editor {
+        let path = path_to_save.display().to_string();
+        projects_db::update_project(&path, |p| p.editor = Some(editor.to_string()))?;
+    }
     execute!(
         stdout,
         Print(ICON_CHECK.green()),
@@ -81,221 +190,912 @@ fn add_project(stdout: &mut Stdout, path: &PathBuf) -> io::Result<()> {
     Ok(())
 }
 
-fn new_project(stdout: &mut Stdout) -> io::Result<()> {
-    let mut input_widget: Input<Rc<RefCell<String>>> = Input::new(
+#[derive(Default, Clone)]
+struct NewProjectState {
+    name: String,
+    template: Option<String>,
+}
+
+/// Check that `name` can be the name of a single folder on every system.
+fn validate_project_name(name: &str) -> io::Result<()> {
+    const RESERVED_CHARS: [char; 9] = ['/', '\\', '<', '>', ':', '"', '|', '?', '*'];
+
+    let invalid = |message: &str| {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' is not a valid project name: {}", name, message),
+        ))
+    };
+    if name.trim().is_empty() {
+        return invalid("it is empty");
+    }
+    if name == "." || name == ".." {
+        return invalid("it is a special folder");
+    }
+    if let Some(c) = name.chars().find(|c| RESERVED_CHARS.contains(c)) {
+        return invalid(&format!("it contains '{}'", c));
+    }
+    if name.chars().any(|c| c.is_control()) {
+        return invalid("it contains control characters");
+    }
+    if name != name.trim() || name.ends_with('.') {
+        return invalid("it starts or ends with a space, or ends with a dot");
+    }
+    Ok(())
+}
+
+/// Get the folder of the new project, checking that it can be created.
+fn new_project_path(parent: Option<&Path>, name: &str) -> io::Result<PathBuf> {
+    validate_project_name(name)?;
+    let parent = match parent {
+        Some(parent) => canonicalize_path(parent).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Cannot use the folder {}: {}", parent.display(), e),
+            )
+        })?,
+        None => env::current_dir()?,
+    };
+    if !parent.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a folder", parent.display()),
+        ));
+    }
+
+    let path = parent.join(name);
+    if path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", path.display()),
+        ));
+    }
+    Ok(path)
+}
+
+fn new_project(
+    stdout: &mut Stdout,
+    name: Option<&str>,
+    parent: Option<&Path>,
+    template: Option<&str>,
+) -> io::Result<()> {
+    // Fail before asking anything when the arguments are wrong
+    if let Some(name) = name {
+        new_project_path(parent, name)?;
+    }
+    let templates = template::list_templates()?;
+    if let Some(name) = template.filter(|t| !templates.iter().any(|n| n == t)) {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "The template '{}' does not exist, the templates are folders in {}",
+                name,
+                template::templates_folder()?.display()
+            ),
+        ));
+    }
+
+    let mut input_widget: Input<Rc<RefCell<NewProjectState>>> = Input::new(
         IconAndLabel(ICON_QUESTION, "Name of the new project: "),
         IconAndLabel(ICON_CHECK, "Name of the new project: "),
     );
 
     input_widget.after(move |input_state, global_state| {
-        if input_state.complete_input && input_state.input.len() > 0 {
-            *(*global_state).borrow_mut() = input_state.input.to_owned();
+        if input_state.complete_input && !input_state.input.is_empty() {
+            (*global_state).borrow_mut().name = input_state.input.to_owned();
             return Action::Next;
         }
         Action::KeepSection
     });
 
     //Render:
-    let mut render_view = view::SectionsView::new(String::new());
-    render_view.child(input_widget);
-    render_view.render(stdout)?;
+    let mut render_view = view::SectionsView::new(NewProjectState {
+        name: name.unwrap_or_default().to_string(),
+        template: template.map(|t| t.to_string()),
+    });
+    if name.is_none() {
+        render_view.child(input_widget);
+    }
 
-    let input_content = (&*render_view.global_state).borrow();
+    // Without the flag, the user chooses one of the templates
+    let ask_template = template.is_none() && !templates.is_empty();
+    if ask_template {
+        let mut template_list: FilterList<Rc<RefCell<NewProjectState>>> =
+            FilterList::new(templates);
+        template_list.add_text_init(ICON_QUESTION, "Template of the project: ");
+        template_list.add_text_final(ICON_CHECK, "Template of the project: ");
+        template_list.add_cancel_option("No template");
+        template_list.after(|list_state, global_state| {
+            if list_state.is_selected {
+                if !list_state.is_cancelled {
+                    (*global_state).borrow_mut().template = list_state.current_option.clone();
+                }
+                return Action::Next;
+            }
+            Action::KeepSection
+        });
+        render_view.child(template_list);
+    }
+    // A view without widgets never ends
+    if name.is_none() || ask_template {
+        render_view.render(stdout)?;
+    }
 
-    let mut new_path = env::current_dir()?;
-    new_path.push(input_content.to_string());
-    projects_db::append_to_first_project(&new_path)?;
-    fs::create_dir(new_path)?;
-    execute!(stdout, Print("\nNew Folder create and save"))?;
-    Ok(())
-}
+    let state = (*render_view.global_state).borrow();
+    if state.name.is_empty() {
+        return Ok(());
+    }
 
-#[derive(Default, Clone)]
-struct GlobalState {
-    project_selected: Option<String>,
-    remove_folder_project: bool,
+    let new_path = new_project_path(parent, &state.name)?;
+    fs::create_dir(&new_path)?;
+    if let Some(template) = &state.template {
+        execute!(
+            stdout,
+            Print(format!(
+                "\nCopying the template {}\n",
+                template.clone().cyan()
+            ))
+        )?;
+        // The folder was just created, so nothing of the user is lost
+        if let Err(e) = template::apply_template(template, &new_path, &state.name) {
+            fs::remove_dir_all(&new_path)?;
+            return Err(e);
+        }
+    }
+
+    // Saved last, so a failure never leaves a project without folder
+    projects_db::append_to_first_project(&canonicalize_path(&new_path)?)?;
+    execute!(
+        stdout,
+        Print("\nCreated and saved "),
+        Print(new_path.display().to_string().green())
+    )?;
+    Ok(())
 }
 
 fn remove_project(stdout: &mut Stdout) -> io::Result<()> {
-    let binding = projects_db::get_projects_content()?;
-    let mut projects: Vec<&str> = binding.trim().lines().collect();
-    if projects.len() <= 0 {
+    let projects = projects_db::get_projects()?;
+    if projects.is_empty() {
         execute!(stdout, Print("You don't have a saved project yet."))?;
         return Ok(());
     }
-    projects.push("None");
 
-    // List of all projects to select
-    let mut list: ListSelected<Rc<RefCell<GlobalState>>> = ListSelected::new(projects);
-    list.add_text_init(ICON_QUESTION, "Select the project to delete: ");
-    list.add_text_final(ICON_CHECK, "Selected option: ");
+    let project = match select_project(stdout, &projects, "Select the project to delete: ")? {
+        Some(p) => p,
+        None => return Ok(()),
+    };
+    execute!(stdout, Print("\n"))?;
+
+    let path = Path::new(&project.path);
+    let mut move_to_trash =
+        path.is_dir() && confirm(stdout, "Also move the folder to the trash: ")?;
+    if move_to_trash {
+        if let Some(status) = git::status(path).filter(|s| s.has_unsaved_work()) {
+            execute!(
+                stdout,
+                Print(format!(
+                    "\n{} {} has {} uncommitted changes and {} unpushed commits\n",
+                    "Warning:".yellow(),
+                    project.path,
+                    status.changes,
+                    status.unpushed
+                ))
+            )?;
+            move_to_trash = confirm(stdout, "Move it to the trash anyway: ")?;
+        }
+    }
 
-    list.after(|list_state, global_data| {
+    let entry = trash::remove(&project, move_to_trash)?;
+    projects_db::delete_project(path)?;
+    execute!(stdout, Print("\nProject removed!"))?;
+    if let (Some(name), Some(trash)) = (&entry.trashed_folder, &entry.trash) {
+        execute!(
+            stdout,
+            Print(format!(
+                "\nFolder moved to {}",
+                trash.join("files").join(name).display()
+            ))
+        )?;
+    }
+    execute!(
+        stdout,
+        Print("\nBring it back with `wflow tidy restore`\n".dark_grey())
+    )?;
+    Ok(())
+}
+
+fn restore_project(stdout: &mut Stdout) -> io::Result<()> {
+    let entries = trash::get_entries()?;
+    if entries.is_empty() {
+        execute!(stdout, Print("There is no removed project."))?;
+        return Ok(());
+    }
+
+    // The most recent removal first
+    let entries: Vec<trash::TrashEntry> = entries.into_iter().rev().collect();
+    let options: Vec<String> = entries
+        .iter()
+        .map(|e| {
+            let kind = match e.trashed_folder {
+                Some(_) => "with its folder",
+                None => "only the entry",
+            };
+            format!(
+                "{} ({}, removed {})",
+                e.project.path,
+                kind,
+                e.removed_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+            )
+        })
+        .collect();
+
+    let mut list: FilterList<Rc<RefCell<Option<usize>>>> = FilterList::new(options);
+    list.add_text_init(ICON_QUESTION, "Select the project to restore: ");
+    list.add_text_final(ICON_CHECK, "Selected option: ");
+    list.add_cancel_option("None");
+    list.after(|list_state, global_state| {
         if list_state.is_selected {
-            if list_state.offset == list_state.length - 1 {
+            if list_state.is_cancelled {
                 return Action::Exit;
             }
-            (*global_data).borrow_mut().project_selected = list_state.current_option.clone();
+            *(*global_state).borrow_mut() = list_state.index;
             return Action::Next;
         }
         Action::KeepSection
     });
 
-    // List to render: 'Yes' | 'No'
-    let mut remove_folder_list: ListSelected<Rc<RefCell<GlobalState>>> =
-        widgets::ListSelected::new(vec!["Yes", "No"]);
-    remove_folder_list.add_text_init(ICON_QUESTION, "Also delete the folder: ");
-    remove_folder_list.add_text_final(ICON_CHECK, "Also delete the folder: ");
+    let mut render_view = view::SectionsView::new(None);
+    render_view.child(list);
+    render_view.render(stdout)?;
+
+    let selected = *render_view.global_state.borrow();
+    let entry = match selected.and_then(|i| entries.get(i)) {
+        Some(e) => e,
+        None => return Ok(()),
+    };
+
+    trash::restore(entry)?;
+    let mut projects = projects_db::get_projects()?;
+    if !projects.iter().any(|p| p.path == entry.project.path) {
+        projects.insert(0, entry.project.clone());
+        projects_db::write_projects(&projects)?;
+    }
+    execute!(
+        stdout,
+        Print(format!(
+            "\n{}Restored {}\n",
+            ICON_CHECK.green(),
+            entry.project.path.clone().green()
+        ))
+    )?;
+    Ok(())
+}
+
+/// Let the user choose one of `projects` with a list.
+///
+/// Returns `None` when the user chooses the "None" option.
+fn select_project(
+    stdout: &mut Stdout,
+    projects: &[Project],
+    label: &'static str,
+) -> io::Result<Option<Project>> {
+    let options: Vec<String> = projects.iter().map(|p| p.path.clone()).collect();
 
-    remove_folder_list.after(|list_state, global_state| {
-        (*global_state).borrow_mut().remove_folder_project =
-            if list_state.offset == 0 { true } else { false };
+    let mut list: FilterList<Rc<RefCell<Option<usize>>>> = FilterList::new(options);
+    list.add_text_init(ICON_QUESTION, label);
+    list.add_text_final(ICON_CHECK, "Selected option: ");
+    list.add_cancel_option("None");
 
+    list.after(|list_state, global_state| {
         if list_state.is_selected {
+            if list_state.is_cancelled {
+                return Action::Exit;
+            }
+            *(*global_state).borrow_mut() = list_state.index;
             return Action::Next;
         }
         Action::KeepSection
     });
 
-    let mut remove_text: TextBlock<Rc<RefCell<GlobalState>>> = TextBlock::new("Removing...");
+    let mut render_view = view::SectionsView::new(None);
+    render_view.child(list);
+    render_view.render(stdout)?;
 
-    remove_text.after(|local_state, global_state| {
-        let context_state = &(*global_state).borrow_mut();
+    let selected = *render_view.global_state.borrow();
+    Ok(selected.and_then(|i| projects.get(i).cloned()))
+}
 
-        let selected_project = match &context_state.project_selected {
-            Some(p) => p.to_owned(),
-            None => {
-                local_state.text.push_str("\nNot exist the project!");
-                return Action::Exit;
-            }
-        };
+/// Get the projects matching `query`, or all of them without a query.
+///
+/// When a single project is the answer, it is the only one returned.
+fn projects_for_query(query: Option<&str>, sort: ProjectSort) -> io::Result<Vec<Project>> {
+    let query = match query {
+        Some(q) => q,
+        None => {
+            let mut projects = projects_db::get_projects()?;
+            projects_db::sort_projects(&mut projects, sort);
+            return Ok(projects);
+        }
+    };
 
-        let res = projects_db::delete_project(Path::new(&selected_project));
+    let matches = projects_db::search_projects(query, sort)?;
+    if matches.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No project matches '{}'", query),
+        ));
+    }
 
-        if let Err(e) = res {
-            local_state.text.push_str(&format!("\n{}", e.to_string()));
-            return Action::Exit;
-        }
-        local_state.text.push_str("\nProject removed!");
+    // An exact name wins over the other fuzzy matches
+    let exact: Vec<&Project> = matches.iter().filter(|p| p.is_named(query)).collect();
+    if let [project] = exact.as_slice() {
+        return Ok(vec![(*project).clone()]);
+    }
+    Ok(matches)
+}
 
-        if context_state.remove_folder_project {
-            let path_opt = &context_state.project_selected;
+fn open_project(
+    stdout: &mut Stdout,
+    query: Option<&str>,
+    editor: Option<&str>,
+    sort: ProjectSort,
+) -> io::Result<()> {
+    let projects = projects_for_query(query, sort)?;
+    if projects.is_empty() {
+        execute!(stdout, Print("You don't have a saved project yet."))?;
+        return Ok(());
+    }
 
-            let path_folder = match path_opt {
-                Some(p) => p.to_owned(),
-                None => {
-                    local_state.text.push_str("\nUnexpected error ocurred");
-                    return Action::Exit;
-                }
-            };
+    let selected = match (query, projects.as_slice()) {
+        (Some(_), [project]) => Some(project.clone()),
+        _ => select_project(stdout, &projects, "Select the project to open: ")?,
+    };
 
-            let path = Path::new(path_folder.as_str());
+    // The editor runs after the view, so terminal editors get a clean screen
+    match selected {
+        Some(project) => launch_project(stdout, &project, editor),
+        None => Ok(()),
+    }
+}
 
-            if !path.is_dir() {
-                local_state.text.push_str("\nThe path is not a folder");
-                return Action::Exit;
-            }
+/// Print the path of the chosen project, or write it to `out`.
+///
+/// The shell function of `wflow init` reads it from `out`, the picker keeps
+/// the terminal on stdout.
+fn print_project_path(
+    stdout: &mut Stdout,
+    query: Option<&str>,
+    out: Option<&Path>,
+) -> io::Result<()> {
+    let projects = projects_for_query(query, ProjectSort::default())?;
+    if projects.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "You don't have a saved project yet.",
+        ));
+    }
 
-            if let Err(e) = fs::remove_dir_all(path) {
-                local_state.text.push_str(&format!("\n{}", e.to_string()));
-                return Action::Exit;
-            }
-            local_state.text.push_str("\nFolder removed!!");
+    let selected = match (query, projects.as_slice()) {
+        (Some(_), [project]) => Some(project.clone()),
+        _ => {
+            let selected = select_project(stdout, &projects, "Select the project to go to: ")?;
+            execute!(stdout, Print("\n"))?;
+            selected
         }
+    };
+    let project = match selected {
+        Some(p) => p,
+        None => return Ok(()),
+    };
+
+    projects_db::record_open(&project.path)?;
+    match out {
+        Some(out) => utils::write_file(out, &project.path),
+        None => execute!(stdout, Print(format!("{}\n", project.path))),
+    }
+}
+
+fn launch_project(stdout: &mut Stdout, project: &Project, editor: Option<&str>) -> io::Result<()> {
+    if !Path::new(&project.path).is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "The folder {} does not exist anymore, check your projects with `wflow tidy doctor`",
+                project.path
+            ),
+        ));
+    }
+    let editor = launcher::resolve_editor(editor, project);
+    projects_db::record_open(&project.path)?;
+    execute!(
+        stdout,
+        Print(format!("\nOpening with {}...\n", editor.clone().cyan()))
+    )?;
+    launcher::open_with_editor(&editor, &project.path)?;
+    Ok(())
+}
 
-        Action::Next
+/// Ask a Yes/No question, `No` is the answer when the user leaves.
+fn confirm(stdout: &mut Stdout, label: &'static str) -> io::Result<bool> {
+    let mut list: ListSelected<Rc<RefCell<bool>>> = widgets::ListSelected::new(vec!["Yes", "No"]);
+    list.add_text_init(ICON_QUESTION, label);
+    list.add_text_final(ICON_CHECK, label);
+    list.after(|list_state, global_state| {
+        *(*global_state).borrow_mut() = list_state.offset == 0;
+        if list_state.is_selected {
+            return Action::Next;
+        }
+        Action::KeepSection
     });
 
-    let mut render_view = view::SectionsView::new(GlobalState::default());
+    let mut render_view = view::SectionsView::new(false);
     render_view.child(list);
-    render_view.child(remove_folder_list);
-    render_view.child(remove_text);
     render_view.render(stdout)?;
+    let answer = *render_view.global_state.borrow();
+    Ok(answer)
+}
+
+fn scan_projects(
+    stdout: &mut Stdout,
+    dir: Option<&Path>,
+    depth: Option<usize>,
+    yes: bool,
+) -> io::Result<()> {
+    let root = match dir {
+        Some(dir) => canonicalize_path(dir)?,
+        None => env::current_dir()?,
+    };
+    let depth = depth.unwrap_or_else(|| config::get().scan_depth());
+
+    let saved: Vec<PathBuf> = projects_db::get_projects()?
+        .iter()
+        .map(|p| doctor::resolved_path(&p.path))
+        .collect();
+    let found: Vec<PathBuf> = scan::find_projects(&root, depth)
+        .into_iter()
+        .filter_map(|p| canonicalize_path(p).ok())
+        .filter(|p| !saved.contains(p))
+        .collect();
+    if found.is_empty() {
+        execute!(
+            stdout,
+            Print(format!("No new project in {}\n", root.display()))
+        )?;
+        return Ok(());
+    }
 
+    let chosen: Vec<PathBuf> = if yes {
+        found
+    } else {
+        let options: Vec<String> = found.iter().map(|p| p.display().to_string()).collect();
+        let mut list: MultiSelect<Rc<RefCell<Vec<bool>>>> = MultiSelect::new(options);
+        list.add_text_init(ICON_QUESTION, "Projects to add: ");
+        list.add_text_final(ICON_CHECK, "Projects to add: ");
+        list.after(|list_state, global_state| {
+            if list_state.is_selected {
+                if !list_state.is_cancelled {
+                    *(*global_state).borrow_mut() = list_state.checked.clone();
+                }
+                return Action::Next;
+            }
+            Action::KeepSection
+        });
+
+        let mut render_view = view::SectionsView::new(Vec::new());
+        render_view.child(list);
+        render_view.render(stdout)?;
+
+        let checked = render_view.global_state.borrow().clone();
+        found
+            .into_iter()
+            .zip(checked)
+            .filter_map(|(path, checked)| checked.then_some(path))
+            .collect()
+    };
+
+    // Added in reverse, so the list keeps the order of the scan
+    for path in chosen.iter().rev() {
+        projects_db::append_to_first_project(path)?;
+    }
+    execute!(
+        stdout,
+        Print(format!(
+            "\n{}Added {} projects\n",
+            ICON_CHECK.green(),
+            chosen.len()
+        ))
+    )?;
     Ok(())
 }
 
-fn open_project(stdout: &mut Stdout) -> io::Result<()> {
-    let binding = projects_db::get_projects_content()?;
-    let mut projects: Vec<&str> = binding.trim().lines().collect();
-    if projects.len() <= 0 {
-        execute!(stdout, Print("You don't have a saved project yet."))?;
+fn doctor_projects(stdout: &mut Stdout) -> io::Result<()> {
+    let projects = projects_db::get_projects()?;
+    let problems = doctor::check_projects(&projects);
+    if problems.is_empty() {
+        execute!(
+            stdout,
+            Print(format!(
+                "{}All the {} projects are fine\n",
+                ICON_CHECK.green(),
+                projects.len()
+            ))
+        )?;
         return Ok(());
     }
-    projects.push("None");
 
-    // List of all projects to select
-    let mut list: ListSelected<Rc<RefCell<Option<String>>>> = ListSelected::new(projects);
-    list.add_text_init(ICON_QUESTION, "Select the project to delete: ");
-    list.add_text_final(ICON_CHECK, "Selected option: ");
+    print_problems(stdout, &projects, &problems)?;
+    execute!(
+        stdout,
+        Print(format!(
+            "\n{} of {} projects have problems, fix them with `wflow tidy prune`\n",
+            problems.len(),
+            projects.len()
+        ))
+    )?;
+    Ok(())
+}
 
-    list.after(|list_state, global_state| {
-        if list_state.is_selected {
-            if list_state.offset == list_state.length - 1 {
-                return Action::Exit;
-            }
-            *(*global_state).borrow_mut() = list_state.current_option.clone();
-            return Action::Next;
+fn print_problems(
+    stdout: &mut Stdout,
+    projects: &[Project],
+    problems: &[(usize, doctor::Problem)],
+) -> io::Result<()> {
+    for (index, problem) in problems {
+        execute!(
+            stdout,
+            Print(format!("{} ", "x".red())),
+            Print(&projects[*index].path),
+            Print(format!(": {}", problem).dark_grey()),
+            Print("\n")
+        )?;
+    }
+    Ok(())
+}
+
+/// Remove the missing, duplicate and non-folder projects, and save the real
+/// path of the moved ones. Their todos follow the new paths.
+fn prune_projects(stdout: &mut Stdout, yes: bool) -> io::Result<()> {
+    let projects = projects_db::get_projects()?;
+    let problems = doctor::check_projects(&projects);
+    if problems.is_empty() {
+        execute!(stdout, Print("Nothing to prune\n"))?;
+        return Ok(());
+    }
+
+    print_problems(stdout, &projects, &problems)?;
+    if !yes && !confirm(stdout, "Prune these projects? ")? {
+        return Ok(());
+    }
+
+    let removed = problems
+        .iter()
+        .filter(|(_, p)| !matches!(p, doctor::Problem::Moved(_)))
+        .count();
+    let (kept, renamed) = doctor::fix_projects(projects, &problems);
+    projects_db::write_projects(&kept)?;
+    let new_path = |path: &str| match renamed.iter().find(|(old, _)| old == path) {
+        Some((_, new)) => new.clone(),
+        None => path.to_string(),
+    };
+
+    let mut todos = todo_db::get_todos()?;
+    for todo in &mut todos {
+        if let Some(path) = &todo.project {
+            todo.project = Some(new_path(path));
         }
-        Action::KeepSection
-    });
+    }
+    todo_db::write_todos(&todos)?;
 
-    let mut text_open: TextBlock<Rc<RefCell<Option<String>>>> = TextBlock::new("Open...");
-    text_open.after(|local_state, global_state| {
-        let project_selected = &*(global_state).borrow_mut();
-
-        match project_selected {
-            Some(s) => {
-                let path = s.as_str();
-                let res = open_code(path);
-                match res {
-                    Ok(_c) => {
-                        local_state.text.push_str("\nOpened!!");
-                        return Action::Next;
-                    }
-                    Err(e) => {
-                        local_state.text.push_str(&format!("\n{}", e.to_string()));
-                        return Action::Exit;
-                    }
-                }
-            }
-            None => {
-                return Action::Exit;
-            }
-        };
+    execute!(
+        stdout,
+        Print(format!(
+            "\n{}Removed {} and fixed {} projects\n",
+            ICON_CHECK.green(),
+            removed,
+            problems.len() - removed
+        ))
+    )?;
+    Ok(())
+}
+
+fn edit_project(stdout: &mut Stdout, args: &EditProjectArgs) -> io::Result<()> {
+    let query = args.query.as_deref();
+    let projects = projects_for_query(query, ProjectSort::default())?;
+    if projects.is_empty() {
+        execute!(stdout, Print("You don't have a saved project yet."))?;
+        return Ok(());
+    }
+
+    let selected = match (query, projects.as_slice()) {
+        (Some(_), [project]) => Some(project.clone()),
+        _ => select_project(stdout, &projects, "Select the project to edit: ")?,
+    };
+    let project = match selected {
+        Some(p) => p,
+        None => return Ok(()),
+    };
+
+    if !args.has_changes() {
+        return display_project(stdout, &project);
+    }
+
+    let mut edited = project.clone();
+    projects_db::update_project(&project.path, |p| {
+        apply_project_changes(p, args);
+        edited = p.clone();
+    })?;
+    execute!(
+        stdout,
+        Print(format!("Project {} updated\n", edited.name().green()))
+    )?;
+    display_project(stdout, &edited)
+}
+
+/// Apply the changes asked in `args`, an empty value removes the field.
+fn apply_project_changes(project: &mut Project, args: &EditProjectArgs) {
+    let non_empty = |value: &String| {
+        let value = value.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    };
+
+    if let Some(name) = &args.name {
+        project.display_name = non_empty(name);
+    }
+    if let Some(description) = &args.description {
+        project.description = non_empty(description);
+    }
+    if let Some(editor) = &args.editor {
+        project.editor = non_empty(editor);
+    }
+
+    project.aliases.retain(|a| {
+        !args
+            .remove_aliases
+            .iter()
+            .any(|r| r.eq_ignore_ascii_case(a))
     });
+    for alias in args.aliases.iter().filter_map(non_empty) {
+        if !project
+            .aliases
+            .iter()
+            .any(|a| a.eq_ignore_ascii_case(&alias))
+        {
+            project.aliases.push(alias);
+        }
+    }
 
-    let mut render_view = view::SectionsView::new(Some(String::new()));
-    render_view.child(list);
-    render_view.child(text_open);
-    render_view.render(stdout)?;
+    project
+        .tags
+        .retain(|t| !args.remove_tags.iter().any(|r| r.eq_ignore_ascii_case(t)));
+    for tag in args.tags.iter().filter_map(non_empty) {
+        if !project.tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            project.tags.push(tag);
+        }
+    }
+}
 
+fn display_project(stdout: &mut Stdout, project: &Project) -> io::Result<()> {
+    let fields = [
+        ("Name", Some(project.name().to_string())),
+        ("Path", Some(project.path.clone())),
+        ("Description", project.description.clone()),
+        ("Aliases", Some(project.aliases.join(", "))),
+        ("Tags", Some(project.tags.join(", "))),
+        ("Editor", project.editor.clone()),
+    ];
+    for (label, value) in fields {
+        let value = value.filter(|v| !v.is_empty()).unwrap_or_default();
+        execute!(
+            stdout,
+            Print(format!("{:<13}", format!("{label}:")).cyan()),
+            Print(format!("{value}\n"))
+        )?;
+    }
     Ok(())
 }
 
-fn display_projects_list(stdout: &mut Stdout) -> io::Result<()> {
-    let projects_string = projects_db::get_projects_content()?;
-    let projects: Vec<&str> = projects_string.trim().lines().collect();
-    if projects.len() <= 0 {
-        execute!(stdout, Print("You don't have a saved project yet."))?;
+fn display_projects_list(
+    stdout: &mut Stdout,
+    tag: Option<&str>,
+    sort: ProjectSort,
+    with_git: bool,
+    format: Option<OutputFormat>,
+) -> io::Result<()> {
+    let mut projects = projects_db::get_projects()?;
+    projects_db::sort_projects(&mut projects, sort);
+    let projects: Vec<&Project> = projects
+        .iter()
+        .filter(|p| tag.is_none_or(|tag| p.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))))
+        .collect();
+
+    if with_git {
+        return display_git_status(stdout, &projects, format);
+    }
+
+    let todos = todo_db::get_todos()?;
+    let open_todos = |project: &Project| {
+        todos
+            .iter()
+            .filter(|t| t.is_open() && t.project.as_ref() == Some(&project.path))
+            .count()
+    };
+
+    if let Some(format) = format {
+        let records: Vec<ProjectRecord> = projects
+            .iter()
+            .map(|p| ProjectRecord {
+                name: p.name(),
+                path: &p.path,
+                aliases: &p.aliases,
+                tags: &p.tags,
+                description: p.description.as_deref(),
+                open_todos: open_todos(p),
+            })
+            .collect();
+        return output::print_records(stdout, format, &records);
+    }
+
+    if projects.is_empty() {
+        let message = match tag {
+            Some(_) => "No project has this tag.",
+            None => "You don't have a saved project yet.",
+        };
+        execute!(stdout, Print(message))?;
         return Ok(());
     }
 
-    let mut count = 0;
-    for project in &projects {
-        execute!(stdout, Print(&format!("{count}: {}\n", project)))?;
-        count += 1;
+    for (count, project) in projects.iter().enumerate() {
+        execute!(
+            stdout,
+            Print(&format!("{count}: {} ", project.name())),
+            Print(project.path.as_str().dark_grey())
+        )?;
+        if !project.tags.is_empty() {
+            let tags: Vec<String> = project.tags.iter().map(|t| format!("#{t}")).collect();
+            execute!(stdout, Print(format!(" {}", tags.join(" ")).cyan()))?;
+        }
+        let open_todos = open_todos(project);
+        if open_todos > 0 {
+            let todos_text = format!(" ({} open todos)", open_todos);
+            execute!(stdout, Print(todos_text.dark_grey()))?;
+        }
+        execute!(stdout, Print("\n"))?;
     }
 
     Ok(())
 }
 
-#[cfg(target_os = "windows")]
-fn open_code(path: &str) -> io::Result<Child> {
-    Command::new("cmd").args(["/C", "code", path]).spawn()
+/// Describe how long ago `date` was, like `3d ago`.
+fn format_age(date: DateTime<Utc>) -> String {
+    let minutes = (Utc::now() - date).num_minutes().max(0);
+    match minutes {
+        0 => "just now".to_string(),
+        1..=59 => format!("{}m ago", minutes),
+        60..=1439 => format!("{}h ago", minutes / 60),
+        1440..=43199 => format!("{}d ago", minutes / 1440),
+        43200..=525599 => format!("{}mo ago", minutes / 43200),
+        _ => format!("{}y ago", minutes / 525600),
+    }
+}
+
+fn display_git_status(
+    stdout: &mut Stdout,
+    projects: &[&Project],
+    format: Option<OutputFormat>,
+) -> io::Result<()> {
+    let statuses: Vec<Option<git::GitStatus>> = projects
+        .iter()
+        .map(|p| git::status(Path::new(&p.path)))
+        .collect();
+
+    if let Some(format) = format {
+        let records: Vec<GitRecord> = projects
+            .iter()
+            .zip(&statuses)
+            .map(|(project, status)| GitRecord {
+                name: project.name(),
+                path: &project.path,
+                is_repository: status.is_some(),
+                branch: status.as_ref().and_then(|s| s.branch.clone()),
+                ahead: status.as_ref().and_then(|s| s.ahead_behind).map(|ab| ab.0),
+                behind: status.as_ref().and_then(|s| s.ahead_behind).map(|ab| ab.1),
+                changes: status.as_ref().map(|s| s.changes),
+                last_commit: status.as_ref().and_then(|s| s.last_commit),
+            })
+            .collect();
+        return output::print_records(stdout, format, &records);
+    }
+
+    if projects.is_empty() {
+        execute!(stdout, Print("You don't have a saved project yet."))?;
+        return Ok(());
+    }
+
+    let body: Vec<Vec<String>> = projects
+        .iter()
+        .zip(&statuses)
+        .map(|(project, status)| {
+            let status = match status {
+                Some(s) => s,
+                None => {
+                    let note = if Path::new(&project.path).is_dir() {
+                        "not a repository"
+                    } else {
+                        "missing folder"
+                    };
+                    return vec![
+                        project.name().to_string(),
+                        note.to_string(),
+                        String::new(),
+                        String::new(),
+                        String::new(),
+                    ];
+                }
+            };
+            let ahead_behind = match status.ahead_behind {
+                Some((ahead, behind)) => format!("+{} -{}", ahead, behind),
+                None => "no upstream".to_string(),
+            };
+            vec![
+                project.name().to_string(),
+                status
+                    .branch
+                    .clone()
+                    .unwrap_or_else(|| "(detached)".to_string()),
+                ahead_behind,
+                status.changes.to_string(),
+                status
+                    .last_commit
+                    .map(format_age)
+                    .unwrap_or_else(|| "no commits".to_string()),
+            ]
+        })
+        .collect();
+
+    let content_table = table(
+        body,
+        vec![
+            "Project".to_string(),
+            "Branch".to_string(),
+            "Ahead/Behind".to_string(),
+            "Changes".to_string(),
+            "Last commit".to_string(),
+        ],
+    );
+
+    for (row, columns) in content_table.iter().enumerate() {
+        // Row 0 holds the headers
+        let status = row.checked_sub(1).and_then(|i| statuses[i].as_ref());
+        for (column, cell) in columns.iter().enumerate() {
+            let cell = match (column, status) {
+                _ if row == 0 => cell.clone().bold().to_string(),
+                (1, None) => cell.clone().dark_grey().to_string(),
+                (2, Some(s)) if s.ahead_behind.is_some_and(|ab| ab != (0, 0)) => {
+                    cell.clone().cyan().to_string()
+                }
+                (3, Some(s)) if s.changes > 0 => cell.clone().yellow().to_string(),
+                _ => cell.clone(),
+            };
+            execute!(stdout, Print(cell), Print(" "))?;
+        }
+        execute!(stdout, Print("\n"))?;
+    }
+    Ok(())
 }
 
-#[cfg(not(target_os = "windows"))]
-fn open_code(path: &str) -> io::Result<Child> {
-    Command::new("cmd").args(["-c", "code", path]).spawn()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_names_are_single_folder_names() {
+        for name in ["app", "my app", "app.v2", "été", "x; y"] {
+            assert!(validate_project_name(name).is_ok(), "{}", name);
+        }
+        for name in [
+            "", "  ", ".", "..", "a/b", r"a\b", "a:b", " app", "app ", "app.", "a\tb",
+        ] {
+            assert!(validate_project_name(name).is_err(), "{:?}", name);
+        }
+    }
 }
@@ -1,75 +1,424 @@
 use std::{
-    env,
-    fs::{self, OpenOptions},
-    io::{self, Read},
+    cmp::Reverse,
+    fs, io,
     path::{Path, PathBuf},
 };
 
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config, constants,
+    utils::{self, fuzzy::fuzzy_match},
+};
+
+/// Version of the schema written to the projects file.
+///
+/// Bump it whenever fields are added to `Project`: older binaries don't
+/// know them and would drop them on their next write.
+///
+/// - 1: path and editor of the projects
+/// - 2: name, aliases, tags, description and opens of the projects
+const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub path: String,
+    /// Name shown for the project, the folder name is used when missing.
+    #[serde(default, rename = "name", skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// Other names accepted when searching the project.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Command used to open this project, instead of the configured editor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    /// Times the project was opened or visited with `tidy cd`.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub open_count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_opened: Option<DateTime<Utc>>,
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+/// Order of the projects in `tidy list` and the pickers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ProjectSort {
+    Name,
+    Path,
+    /// The last opened first
+    Recent,
+    /// The most often and recently opened first
+    #[default]
+    Frecent,
+}
+
+impl Project {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            display_name: None,
+            aliases: Vec::new(),
+            tags: Vec::new(),
+            description: None,
+            editor: None,
+            open_count: 0,
+            last_opened: None,
+        }
+    }
+
+    /// Name of the project, by default the name of its folder.
+    pub fn name(&self) -> &str {
+        if let Some(name) = &self.display_name {
+            return name;
+        }
+        Path::new(&self.path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(&self.path)
+    }
+
+    /// Score mixing how often and how recently the project was opened.
+    ///
+    /// Each open counts less as it gets older, halving every week.
+    pub fn frecency(&self, now: DateTime<Utc>) -> f64 {
+        const HALF_LIFE_HOURS: f64 = 24.0 * 7.0;
+
+        let last_opened = match self.last_opened {
+            Some(date) => date,
+            None => return 0.0,
+        };
+        let age_hours = (now - last_opened).num_minutes().max(0) as f64 / 60.0;
+        f64::from(self.open_count) * 0.5_f64.powf(age_hours / HALF_LIFE_HOURS)
+    }
+
+    /// Whether `name` is the name or one of the aliases of the project.
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name().eq_ignore_ascii_case(name)
+            || self.aliases.iter().any(|a| a.eq_ignore_ascii_case(name))
+    }
+
+    /// Add the data of `other`, a duplicate entry of the same folder.
+    ///
+    /// Aliases, tags and opens are added up, the other fields are only
+    /// taken when this project has none.
+    pub fn merge(&mut self, other: Project) {
+        for alias in other.aliases {
+            if !self.aliases.contains(&alias) {
+                self.aliases.push(alias);
+            }
+        }
+        for tag in other.tags {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+        self.open_count = self.open_count.saturating_add(other.open_count);
+        self.last_opened = self.last_opened.max(other.last_opened);
+        self.display_name = self.display_name.take().or(other.display_name);
+        self.description = self.description.take().or(other.description);
+        self.editor = self.editor.take().or(other.editor);
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProjectStore {
+    version: u32,
+    projects: Vec<Project>,
+}
+
 fn get_folder_root() -> io::Result<PathBuf> {
-    let mut path = env::current_exe()?;
-    path.pop();
-    path.push("projects.txt");
-    return Ok(path);
-}
-
-///Get projects content if already exist, or create instead.
-pub fn get_projects_content() -> io::Result<String> {
-    let path_to_projects = get_folder_root()?;
-    let mut file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .open(path_to_projects.as_path())?;
-    let mut content = String::new();
-    file.read_to_string(&mut content)?;
-    return Ok(content);
-}
-
-pub fn write_projects(content: &str) -> io::Result<()> {
-    let path_to_projects = get_folder_root()?;
-    fs::write(path_to_projects, content)?;
+    if let Some(path) = config::get().projects_file() {
+        return Ok(path);
+    }
+    let mut path = utils::get_data_folder()?;
+    path.push(constants::NAME_PROJECTS_FILE);
+    Ok(path)
+}
+
+fn invalid_data<E: ToString>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+///Get all the saved projects, the most recent first.
+///
+/// If the store does not exist yet, the legacy `projects.txt` is migrated first.
+pub fn get_projects() -> io::Result<Vec<Project>> {
+    let path = get_folder_root()?;
+    if !path.exists() {
+        return migrate_legacy_projects();
+    }
+
+    let content = fs::read_to_string(&path)?;
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    // `tidy.projects_file` used to name a legacy file, with a path per line
+    if !content.trim_start().starts_with('{') {
+        let mut backup = path.clone().into_os_string();
+        backup.push(".bak");
+        fs::copy(&path, backup)?;
+        let projects = parse_legacy_projects(&content);
+        write_projects(&projects)?;
+        return Ok(projects);
+    }
+
+    let store: ProjectStore = serde_json::from_str(&content)
+        .map_err(|e| invalid_data(format!("Cannot read {}: {}", path.display(), e)))?;
+
+    if store.version > SCHEMA_VERSION {
+        return Err(invalid_data(format!(
+            "{} uses schema version {}, but this wflow only supports up to {}",
+            path.display(),
+            store.version,
+            SCHEMA_VERSION
+        )));
+    }
+    if store.version < SCHEMA_VERSION {
+        // Older binaries refuse the migrated file, keep a copy they can read.
+        // The new fields get their defaults, nothing else changes.
+        let backup = path.with_extension(format!("json.v{}.bak", store.version));
+        if !backup.exists() {
+            fs::copy(&path, &backup)?;
+        }
+    }
+    Ok(store.projects)
+}
+
+pub fn write_projects(projects: &[Project]) -> io::Result<()> {
+    let store = ProjectStore {
+        version: SCHEMA_VERSION,
+        projects: projects.to_vec(),
+    };
+    let content = serde_json::to_string_pretty(&store).map_err(invalid_data)?;
+
+    // Write to a temporary file first, so a crash never leaves a truncated store.
+    let path = get_folder_root()?;
+    let tmp_path = path.with_extension("json.tmp");
+    utils::write_file(&tmp_path, &content)?;
+    fs::rename(tmp_path, path)?;
     Ok(())
 }
 
+/// Parse the legacy format of the projects, a path per line.
+fn parse_legacy_projects(content: &str) -> Vec<Project> {
+    content
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .map(Project::new)
+        .collect()
+}
+
+/// Convert the old file with one path per line into the structured store.
+///
+/// The legacy file is kept with a `.bak` extension so nothing is lost.
+fn migrate_legacy_projects() -> io::Result<Vec<Project>> {
+    let mut legacy_path = utils::get_data_folder()?;
+    legacy_path.push(constants::NAME_LEGACY_PROJECTS_FILE);
+
+    if !legacy_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&legacy_path)?;
+    let projects = parse_legacy_projects(&content);
+
+    write_projects(&projects)?;
+    fs::rename(&legacy_path, legacy_path.with_extension("txt.bak"))?;
+    Ok(projects)
+}
+
 ///Append new project to first line.
 ///
 /// If the path already exists, then change the place to first line
 pub fn append_to_first_project(url: &Path) -> io::Result<()> {
-    let projects_content = get_projects_content()?;
-    let project_paths: Vec<&str> = projects_content.lines().collect();
     let path = match url.to_str() {
         Some(p) => p,
-        None => return Err(io::Error::new(io::ErrorKind::Other, "Cannot parse url")),
+        None => return Err(io::Error::other("Cannot parse url")),
+    };
+    let mut projects = get_projects()?;
+
+    let project = match projects.iter().position(|p| p.path == path) {
+        Some(index) => projects.remove(index),
+        None => Project::new(path),
     };
+    projects.insert(0, project);
+    write_projects(&projects)
+}
 
-    if project_paths.contains(&path) {
-        let projects_text = project_paths
-            .iter()
-            .filter(|s| **s != path)
-            .map(|s| *s)
-            .collect::<Vec<&str>>()
-            .join("\n");
+///Change a saved project, returns `false` if the project does not exist.
+pub fn update_project(path: &str, change: impl FnOnce(&mut Project)) -> io::Result<bool> {
+    let mut projects = get_projects()?;
+    match projects.iter_mut().find(|p| p.path == path) {
+        Some(project) => change(project),
+        None => return Ok(false),
+    }
+    write_projects(&projects)?;
+    Ok(true)
+}
 
-        write_projects(&format!("{}\n{}", path, projects_text))?;
-        return Ok(());
+///Count an open of the project, used to sort by frecency.
+pub fn record_open(path: &str) -> io::Result<()> {
+    update_project(path, |project| {
+        project.open_count += 1;
+        project.last_opened = Some(Utc::now());
+    })?;
+    Ok(())
+}
+
+///Sort `projects` by `sort`, the projects that compare equal keep their order.
+pub fn sort_projects(projects: &mut [Project], sort: ProjectSort) {
+    match sort {
+        ProjectSort::Name => projects.sort_by_key(|p| p.name().to_lowercase()),
+        ProjectSort::Path => projects.sort_by(|a, b| a.path.cmp(&b.path)),
+        ProjectSort::Recent => projects.sort_by_key(|p| Reverse(p.last_opened)),
+        ProjectSort::Frecent => {
+            let now = Utc::now();
+            projects.sort_by(|a, b| b.frecency(now).total_cmp(&a.frecency(now)));
+        }
     }
-    // If the new url not exist in the projects saved:
-    write_projects(&format!("{}\n{}", path, project_paths.join("\n")))?;
-    return Ok(());
 }
 
 pub fn delete_project(path: &Path) -> io::Result<()> {
-    let binding = get_projects_content()?;
+    let path = path.display().to_string();
+    let projects: Vec<Project> = get_projects()?
+        .into_iter()
+        .filter(|p| p.path != path)
+        .collect();
+    write_projects(&projects)
+}
 
-    let filtered: Vec<String> = binding
-        .lines()
-        .collect::<Vec<&str>>()
-        .iter()
-        .filter(|p| **p != path.display().to_string().as_str())
-        .map(|s| s.to_string())
+///Find a saved project by its path, name or alias.
+pub fn find_project(query: &str) -> io::Result<Option<Project>> {
+    let query_path = Path::new(query)
+        .canonicalize()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| query.to_string());
+
+    let project = get_projects()?
+        .into_iter()
+        .find(|p| p.path == query_path || p.is_named(query));
+    Ok(project)
+}
+
+///Get the saved project that contains `path`, the innermost one if they are nested.
+pub fn project_containing(path: &Path) -> io::Result<Option<Project>> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    let project = get_projects()?
+        .into_iter()
+        .filter(|p| !p.path.is_empty() && path.starts_with(&p.path))
+        .max_by_key(|p| p.path.len());
+    Ok(project)
+}
+
+///Get the saved projects whose name, aliases or path fuzzy-match `query`, best match first.
+///
+/// Projects with the same score are ordered by `sort`.
+pub fn search_projects(query: &str, sort: ProjectSort) -> io::Result<Vec<Project>> {
+    // A match in the name is worth more than the same match in the path
+    const NAME_BONUS: i64 = 10;
+
+    let mut projects = get_projects()?;
+    sort_projects(&mut projects, sort);
+    let mut matches: Vec<(i64, Project)> = projects
+        .into_iter()
+        .filter_map(|project| {
+            let by_name = std::iter::once(project.name())
+                .chain(project.aliases.iter().map(|a| a.as_str()))
+                .filter_map(|name| fuzzy_match(query, name).map(|m| m.score + NAME_BONUS))
+                .max();
+            let by_path = fuzzy_match(query, &project.path).map(|m| m.score);
+            by_name.max(by_path).map(|score| (score, project))
+        })
         .collect();
+    matches.sort_by_key(|m| Reverse(m.0));
+    Ok(matches.into_iter().map(|(_, p)| p).collect())
+}
 
-    write_projects(&filtered.join("\n"))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
 
-    Ok(())
+    #[test]
+    fn legacy_projects_are_a_path_per_line() {
+        let projects = parse_legacy_projects("/home/me/a\n\n  /home/me/b  \n");
+        let paths: Vec<&str> = projects.iter().map(|p| p.path.as_str()).collect();
+        assert_eq!(paths, vec!["/home/me/a", "/home/me/b"]);
+        assert_eq!(projects[1].name(), "b");
+    }
+
+    fn opened(path: &str, open_count: u32, last_opened: Option<DateTime<Utc>>) -> Project {
+        let mut project = Project::new(path);
+        project.open_count = open_count;
+        project.last_opened = last_opened;
+        project
+    }
+
+    fn paths(projects: &[Project]) -> Vec<&str> {
+        projects.iter().map(|p| p.path.as_str()).collect()
+    }
+
+    #[test]
+    fn frecency_halves_every_week() {
+        let now = Utc::now();
+        let project = opened("/a", 4, Some(now));
+        assert_eq!(project.frecency(now), 4.0);
+        assert!((project.frecency(now + Duration::weeks(1)) - 2.0).abs() < 1e-9);
+        assert!((project.frecency(now + Duration::weeks(2)) - 1.0).abs() < 1e-9);
+        // A date in the future counts as now
+        assert_eq!(project.frecency(now - Duration::days(1)), 4.0);
+    }
+
+    #[test]
+    fn never_opened_projects_score_zero() {
+        let now = Utc::now();
+        assert_eq!(Project::new("/a").frecency(now), 0.0);
+        assert_eq!(opened("/a", 3, None).frecency(now), 0.0);
+        assert_eq!(opened("/a", 0, Some(now)).frecency(now), 0.0);
+    }
+
+    #[test]
+    fn projects_are_sorted_by_each_order() {
+        let now = Utc::now();
+        let sample = vec![
+            opened("/a-work/zeta", 10, Some(now - Duration::weeks(3))),
+            opened("/home/Beta", 2, Some(now - Duration::hours(1))),
+            opened("/alpha", 0, None),
+            opened("/old/gamma", 1, Some(now - Duration::weeks(1))),
+        ];
+        let sorted = |sort| {
+            let mut projects = sample.clone();
+            sort_projects(&mut projects, sort);
+            projects
+        };
+
+        assert_eq!(
+            paths(&sorted(ProjectSort::Name)),
+            vec!["/alpha", "/home/Beta", "/old/gamma", "/a-work/zeta"]
+        );
+        assert_eq!(
+            paths(&sorted(ProjectSort::Path)),
+            vec!["/a-work/zeta", "/alpha", "/home/Beta", "/old/gamma"]
+        );
+        assert_eq!(
+            paths(&sorted(ProjectSort::Recent)),
+            vec!["/home/Beta", "/old/gamma", "/a-work/zeta", "/alpha"]
+        );
+        // 10 opens three weeks ago score 1.25, below 2 opens an hour ago
+        assert_eq!(
+            paths(&sorted(ProjectSort::Frecent)),
+            vec!["/home/Beta", "/a-work/zeta", "/old/gamma", "/alpha"]
+        );
+    }
 }
@@ -0,0 +1,57 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Files and folders found at the root of a project.
+const PROJECT_MARKERS: [&str; 5] = [
+    ".git",
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "go.mod",
+];
+
+/// Folders that hold dependencies or build output, never projects of the user.
+const IGNORED_FOLDERS: [&str; 4] = ["node_modules", "target", "vendor", "__pycache__"];
+
+fn is_project_root(path: &Path) -> bool {
+    PROJECT_MARKERS.iter().any(|m| path.join(m).exists())
+}
+
+/// Find the project roots inside `root`, going down at most `depth` folders.
+///
+/// The folders of a project are not searched, so the crates of a workspace
+/// or the packages of a monorepo are not listed on their own. Hidden
+/// folders and symlinks are skipped.
+pub fn find_projects(root: &Path, depth: usize) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    visit(root, depth, &mut found);
+    found.sort();
+    found
+}
+
+fn visit(folder: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+    if is_project_root(folder) {
+        found.push(folder.to_path_buf());
+        return;
+    }
+    if depth == 0 {
+        return;
+    }
+
+    // Folders that cannot be read are skipped, like a permission error
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        if !is_dir || name.starts_with('.') || IGNORED_FOLDERS.contains(&name.as_ref()) {
+            continue;
+        }
+        visit(&entry.path(), depth - 1, found);
+    }
+}
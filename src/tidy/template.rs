@@ -0,0 +1,184 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use toml_edit::DocumentMut;
+
+use crate::{constants, utils};
+
+/// Placeholder replaced by the name of the new project.
+const NAME_PLACEHOLDER: &str = "{{project_name}}";
+
+/// Variable holding the name of the new project in the post-create commands.
+const NAME_VARIABLE: &str = "WFLOW_PROJECT_NAME";
+
+pub fn templates_folder() -> io::Result<PathBuf> {
+    let mut path = utils::get_config_folder()?;
+    path.push(constants::TEMPLATES_FOLDER_NAME);
+    Ok(path)
+}
+
+/// Names of the templates saved in the config folder, sorted.
+pub fn list_templates() -> io::Result<Vec<String>> {
+    let folder = templates_folder()?;
+    if !folder.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut names: Vec<String> = fs::read_dir(folder)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().to_str().map(|n| n.to_string()))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Copy the template `name` into the empty folder `target`, then run its
+/// post-create commands inside it.
+pub fn apply_template(name: &str, target: &Path, project_name: &str) -> io::Result<()> {
+    let source = templates_folder()?.join(name);
+    if !source.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "The template '{}' does not exist, create it in {}",
+                name,
+                source.display()
+            ),
+        ));
+    }
+
+    copy_folder(&source, target, project_name, true)?;
+    for command in read_commands(&source)? {
+        run_command(&command, target, project_name)?;
+    }
+    Ok(())
+}
+
+/// Copy `source` into `target`, replacing the placeholder in the names and
+/// in the contents of the text files.
+fn copy_folder(source: &Path, target: &Path, project_name: &str, is_root: bool) -> io::Result<()> {
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if is_root && file_name == constants::NAME_TEMPLATE_FILE {
+            continue;
+        }
+
+        let destination = target.join(file_name.replace(NAME_PLACEHOLDER, project_name));
+        if entry.file_type()?.is_dir() {
+            fs::create_dir(&destination)?;
+            copy_folder(&entry.path(), &destination, project_name, false)?;
+            continue;
+        }
+
+        // Binary files are copied as they are
+        let content = fs::read(entry.path())?;
+        match String::from_utf8(content) {
+            Ok(text) => fs::write(&destination, text.replace(NAME_PLACEHOLDER, project_name))?,
+            Err(e) => fs::write(&destination, e.into_bytes())?,
+        }
+    }
+    Ok(())
+}
+
+/// Read the `commands` array of the template file, if the template has one.
+fn read_commands(source: &Path) -> io::Result<Vec<String>> {
+    let path = source.join(constants::NAME_TEMPLATE_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let invalid = |message: String| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Cannot read {}: {}", path.display(), message),
+        )
+    };
+    let document = fs::read_to_string(&path)?
+        .parse::<DocumentMut>()
+        .map_err(|e| invalid(e.to_string()))?;
+
+    let commands = match document.get("commands") {
+        Some(item) => item
+            .as_array()
+            .ok_or_else(|| invalid("`commands` must be an array of strings".to_string()))?,
+        None => return Ok(Vec::new()),
+    };
+    commands
+        .iter()
+        .map(|c| {
+            c.as_str()
+                .map(|c| c.to_string())
+                .ok_or_else(|| invalid("`commands` must be an array of strings".to_string()))
+        })
+        .collect()
+}
+
+/// Replace the placeholder of `command` by a quoted reference to the
+/// variable with the name, so the shell never reads the name as code.
+fn command_with_name_variable(command: &str) -> String {
+    let reference = if cfg!(target_os = "windows") {
+        // Delayed expansion happens after `cmd` has parsed the command
+        format!("\"!{}!\"", NAME_VARIABLE)
+    } else {
+        format!("\"${}\"", NAME_VARIABLE)
+    };
+    command.replace(NAME_PLACEHOLDER, &reference)
+}
+
+fn run_command(command: &str, folder: &Path, project_name: &str) -> io::Result<()> {
+    let script = command_with_name_variable(command);
+    let mut process = if cfg!(target_os = "windows") {
+        let mut c = Command::new("cmd");
+        c.arg("/V:ON").arg("/C").arg(&script);
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(&script);
+        c
+    };
+
+    let status = process
+        .current_dir(folder)
+        .env(NAME_VARIABLE, project_name)
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "The template command '{}' exited with {}",
+            command, status
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_placeholder_of_the_commands_becomes_a_variable() {
+        let command = command_with_name_variable("cargo init --name {{project_name}}");
+        assert!(!command.contains(NAME_PLACEHOLDER));
+        assert!(command.contains(NAME_VARIABLE));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_name_is_never_run_as_a_command() {
+        let folder = std::env::temp_dir().join(format!("wflow-template-{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+
+        let name = "x; touch injected $(touch injected2) 'q\"";
+        run_command("printf %s {{project_name}} > name.txt", &folder, name).unwrap();
+        let written = fs::read_to_string(folder.join("name.txt")).unwrap();
+        let injected = folder.join("injected").exists() || folder.join("injected2").exists();
+        fs::remove_dir_all(&folder).unwrap();
+
+        assert_eq!(written, name);
+        assert!(!injected);
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{constants, utils};
+
+use super::projects_db::Project;
+
+/// A project removed with `tidy remove`, which `tidy restore` brings back.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub project: Project,
+    pub removed_at: DateTime<Utc>,
+    /// Name of the folder in the trash, `None` when only the entry was removed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trashed_folder: Option<String>,
+    /// Trash folder the folder was moved to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trash: Option<PathBuf>,
+}
+
+fn invalid_data<E: ToString>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+fn get_trash_file() -> io::Result<PathBuf> {
+    let mut path = utils::get_data_folder()?;
+    path.push(constants::NAME_TRASH_FILE);
+    Ok(path)
+}
+
+///Get the removed projects, the most recent last.
+pub fn get_entries() -> io::Result<Vec<TrashEntry>> {
+    let path = get_trash_file()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path)?;
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(&content)
+        .map_err(|e| invalid_data(format!("Cannot read {}: {}", path.display(), e)))
+}
+
+fn write_entries(entries: &[TrashEntry]) -> io::Result<()> {
+    let content = serde_json::to_string_pretty(entries).map_err(invalid_data)?;
+    let path = get_trash_file()?;
+    let tmp_path = path.with_extension("json.tmp");
+    utils::write_file(&tmp_path, &content)?;
+    fs::rename(tmp_path, path)
+}
+
+/// Trash folder of the user.
+///
+/// On Linux it is the home trash of the freedesktop.org specification, so
+/// the file managers can restore the folders too. Elsewhere wflow keeps its
+/// own trash in the data folder.
+fn trash_folder() -> io::Result<PathBuf> {
+    if cfg!(target_os = "linux") {
+        let data_home = env::var_os("XDG_DATA_HOME")
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| utils::get_home_folder().map(|home| home.join(".local").join("share")));
+        if let Some(data_home) = data_home {
+            return Ok(data_home.join("Trash"));
+        }
+    }
+    Ok(utils::get_data_folder()?.join("trash"))
+}
+
+/// Name of the folder in the trash, from the folder name and never from
+/// the display name, which can contain `/` or `..`.
+fn trash_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "project".to_string())
+}
+
+/// Encode a path for the `Path` key of a `.trashinfo` file.
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Move a folder, copying it when the trash is on another file system.
+fn move_folder(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_folder(from, to)?;
+            fs::remove_dir_all(from)
+        }
+        result => result,
+    }
+}
+
+fn copy_folder(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let destination = to.join(entry.file_name());
+        #[cfg(unix)]
+        if file_type.is_symlink() {
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &destination)?;
+            continue;
+        }
+        if file_type.is_dir() {
+            copy_folder(&entry.path(), &destination)?;
+        } else {
+            fs::copy(entry.path(), &destination)?;
+        }
+    }
+    Ok(())
+}
+
+/// Record the removal of `project`, moving its folder to the trash when
+/// `move_to_trash` is true.
+pub fn remove(project: &Project, move_to_trash: bool) -> io::Result<TrashEntry> {
+    let mut entry = TrashEntry {
+        project: project.clone(),
+        removed_at: Utc::now(),
+        trashed_folder: None,
+        trash: None,
+    };
+
+    if move_to_trash {
+        let trash = trash_folder()?;
+        let files = trash.join("files");
+        let info = trash.join("info");
+        fs::create_dir_all(&files)?;
+        fs::create_dir_all(&info)?;
+
+        // The name must be free in both folders of the trash
+        let base_name = trash_name(&project.path);
+        let mut name = base_name.clone();
+        let mut count = 1;
+        while files.join(&name).exists() || info.join(format!("{name}.trashinfo")).exists() {
+            count += 1;
+            name = format!("{base_name}.{count}");
+        }
+
+        let info_content = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encode_path(&project.path),
+            entry
+                .removed_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%dT%H:%M:%S")
+        );
+        let info_path = info.join(format!("{name}.trashinfo"));
+        utils::write_file(&info_path, &info_content)?;
+        if let Err(e) = move_folder(Path::new(&project.path), &files.join(&name)) {
+            fs::remove_file(info_path)?;
+            return Err(e);
+        }
+
+        entry.trashed_folder = Some(name);
+        entry.trash = Some(trash);
+    }
+
+    let mut entries = get_entries()?;
+    entries.push(entry.clone());
+    write_entries(&entries)?;
+    Ok(entry)
+}
+
+/// Move the folder of `entry` back to its place and forget the entry.
+pub fn restore(entry: &TrashEntry) -> io::Result<()> {
+    if let (Some(name), Some(trash)) = (&entry.trashed_folder, &entry.trash) {
+        let trashed = trash.join("files").join(name);
+        let original = Path::new(&entry.project.path);
+        if !trashed.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not in the trash anymore", trashed.display()),
+            ));
+        }
+        if original.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", original.display()),
+            ));
+        }
+        if let Some(parent) = original.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_folder(&trashed, original)?;
+
+        let info_path = trash.join("info").join(format!("{name}.trashinfo"));
+        if info_path.exists() {
+            fs::remove_file(info_path)?;
+        }
+    }
+
+    let entries: Vec<TrashEntry> = get_entries()?
+        .into_iter()
+        .filter(|e| !(e.project.path == entry.project.path && e.removed_at == entry.removed_at))
+        .collect();
+    write_entries(&entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trash_names_are_the_folder_name() {
+        assert_eq!(trash_name("/home/me/app"), "app");
+        assert_eq!(trash_name("/home/me/app/"), "app");
+        assert_eq!(trash_name("/"), "project");
+        assert_eq!(trash_name("/home/me/.."), "project");
+    }
+
+    #[test]
+    fn trash_paths_are_percent_encoded() {
+        assert_eq!(encode_path("/home/me/app-1_v.2~"), "/home/me/app-1_v.2~");
+        assert_eq!(encode_path("/home/me/my app"), "/home/me/my%20app");
+        assert_eq!(encode_path("/tmp/été"), "/tmp/%C3%A9t%C3%A9");
+        assert_eq!(encode_path("/a%b"), "/a%25b");
+    }
+}
@@ -1,16 +1,22 @@
+#[allow(clippy::module_inception)]
 pub mod todo;
+pub mod todo_db;
 pub mod todo_utils;
 
 use std::{
     cell::RefCell,
-    io::{self, stdout, Stdout},
+    cmp::Reverse,
+    collections::BTreeMap,
+    env, fs,
+    io::{self, stdout, Stdout, Write},
     rc::Rc,
 };
 
+use chrono::{Local, NaiveDate};
 use cli_printer::{
     core::{
         interfaces::WidgetRoot,
-        utils::{Action, IconAndLabel, RenderWidget},
+        utils::{Action, IconAndLabel},
         view::SectionsView,
     },
     styles::{ICON_CHECK, ICON_QUESTION},
@@ -20,71 +26,205 @@ use crossterm::{
     execute,
     style::{Print, Stylize},
 };
-
-use crate::{cli::TodoCommands, constants, custom_widgets, utils};
+use serde::Serialize;
+
+use crate::{
+    cli::{ArchiveCommands, CreateTodoArgs, TodoCommands},
+    config,
+    custom_widgets::{self, TextInput},
+    tidy::projects_db,
+    utils::{
+        self,
+        output::{self, OutputFormat, Record},
+    },
+};
 
 use self::{
-    todo::{Todo, TodoState},
+    todo::{find_todo, sort_todos, DueState, Priority, TagFilter, Todo, TodoSort, TodoState},
     todo_utils::table,
 };
 
+/// A todo in the `--format` output of `todo list`.
+#[derive(Serialize)]
+struct TodoRecord<'a> {
+    id: u32,
+    description: &'a str,
+    status: &'static str,
+    priority: Priority,
+    tags: &'a [String],
+    due: Option<NaiveDate>,
+    project: Option<&'a str>,
+}
+
+impl<'a> From<&'a Todo> for TodoRecord<'a> {
+    fn from(todo: &'a Todo) -> Self {
+        Self {
+            id: todo.id,
+            description: &todo.description,
+            status: todo.status.key(),
+            priority: todo.priority,
+            tags: &todo.tags,
+            due: todo.due,
+            project: todo.project.as_deref(),
+        }
+    }
+}
+
+impl Record for TodoRecord<'_> {
+    const FIELDS: &'static [&'static str] = &[
+        "id",
+        "description",
+        "status",
+        "priority",
+        "tags",
+        "due",
+        "project",
+    ];
+
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.description.to_string(),
+            self.status.to_string(),
+            self.priority.to_string(),
+            self.tags.join(","),
+            self.due.map(|d| d.to_string()).unwrap_or_default(),
+            self.project.unwrap_or_default().to_string(),
+        ]
+    }
+}
+
 pub struct TodoProgram;
 
 impl TodoProgram {
-    pub fn run(todo_command: &TodoCommands) -> io::Result<()> {
+    pub fn run(todo_command: &TodoCommands, format: Option<OutputFormat>) -> io::Result<()> {
         let mut stdout = stdout();
         match todo_command {
-            TodoCommands::List => {
-                let res = list_todo(&mut stdout);
-                if let Err(e) = res {
-                    println!("{}", e.to_string())
-                }
-                Ok(())
+            TodoCommands::List { global, sort, tags } => {
+                let filter = TagFilter::new(tags);
+                list_todo(&mut stdout, *global, *sort, &filter, format)
             }
-            TodoCommands::Create => {
-                let res = create_todo(&mut stdout);
-                if let Err(e) = res {
-                    println!("{}", e.to_string())
-                }
-                Ok(())
+            TodoCommands::Create(args) => create_todo(&mut stdout, args),
+            TodoCommands::Check {
+                query: None,
+                global,
+            } => change_todo(&mut stdout, *global),
+            TodoCommands::Check {
+                query: Some(query), ..
+            } => set_todo_state(&mut stdout, query, |_| TodoState::Completed),
+            TodoCommands::Uncheck { query } => {
+                set_todo_state(&mut stdout, query, |_| TodoState::NoStarted)
+            }
+            TodoCommands::Toggle { query } => {
+                set_todo_state(&mut stdout, query, |state| match state {
+                    TodoState::Completed => TodoState::NoStarted,
+                    _ => TodoState::Completed,
+                })
             }
-            TodoCommands::Check => {
-                let res = change_todo(&mut stdout);
-                if let Err(e) = res {
-                    println!("{}", e.to_string())
+            TodoCommands::Agenda { global } => show_agenda(&mut stdout, *global),
+            TodoCommands::Tags { global } => list_tags(&mut stdout, *global),
+            TodoCommands::Edit { query, editor } => edit_todo(&mut stdout, query, *editor),
+            TodoCommands::Delete { queries } => delete_todos(&mut stdout, queries),
+            TodoCommands::Archive { command } => match command {
+                ArchiveCommands::List { global, since } => {
+                    list_archive(&mut stdout, *global, *since)
                 }
-                Ok(())
+                ArchiveCommands::Restore { id } => restore_todo(&mut stdout, *id),
+            },
+            TodoCommands::Priority { query, priority } => {
+                set_todo_priority(&mut stdout, query, *priority)
             }
         }
     }
 }
 
-fn list_todo(stdout: &mut Stdout) -> io::Result<()> {
-    let mut program_path = utils::get_folder_program()?;
-    program_path.push(constants::NAME_TODOS_FILE);
+/// Project the todo commands work on: the saved project containing the
+/// current directory, unless `global` is set.
+fn current_project(global: bool) -> io::Result<Option<String>> {
+    if global {
+        return Ok(None);
+    }
+    let project = projects_db::project_containing(&env::current_dir()?)?;
+    Ok(project.map(|p| p.path))
+}
 
-    let todo_content = utils::get_content_file(&program_path)?;
+/// Get the todos of `project`, or all of them when there is no project.
+fn get_scoped_todos(project: &Option<String>) -> io::Result<Vec<Todo>> {
+    let todos = todo_db::get_todos()?;
+    match project {
+        Some(_) => Ok(todos
+            .into_iter()
+            .filter(|t| &t.project == project)
+            .collect()),
+        None => Ok(todos),
+    }
+}
+
+fn list_todo(
+    stdout: &mut Stdout,
+    global: bool,
+    sort: TodoSort,
+    filter: &TagFilter,
+    format: Option<OutputFormat>,
+) -> io::Result<()> {
+    let project = current_project(global)?;
+    let mut todos = get_scoped_todos(&project)?;
+    todos.retain(|t| filter.matches(t));
+    sort_todos(&mut todos, sort);
+
+    if let Some(format) = format {
+        let records: Vec<TodoRecord> = todos.iter().map(TodoRecord::from).collect();
+        return output::print_records(stdout, format, &records);
+    }
 
-    if todo_content.trim().len() == 0 {
+    if let Some(project) = &project {
+        execute!(
+            stdout,
+            Print(format!("Todos of {}\n", project.clone().cyan()))
+        )?;
+    }
+    if todos.is_empty() {
         return Ok(());
     }
-    let todos_collect: Vec<Vec<String>> = todo_content
-        .trim()
-        .lines()
-        .map(|f| {
-            let todo = Todo::try_from(f).unwrap();
-            vec![todo.icon, todo.description, todo.status.to_string()]
+    let today = Local::now().date_naive();
+    let priorities: Vec<Priority> = todos.iter().map(|t| t.priority).collect();
+    let due_states: Vec<Option<DueState>> = todos.iter().map(|t| t.due_state(today)).collect();
+    let todos_collect: Vec<Vec<String>> = todos
+        .into_iter()
+        .map(|todo| {
+            vec![
+                todo.id.to_string(),
+                todo.icon().to_string(),
+                todo.priority.to_string(),
+                todo.description,
+                todo.status.to_string(),
+                todo.due.map(|d| d.to_string()).unwrap_or_default(),
+            ]
         })
         .collect();
 
     let content_table = table(
         todos_collect,
-        vec!["Icon".to_string(), "Todo".to_string(), "Status".to_string()],
+        vec![
+            "ID".to_string(),
+            "Icon".to_string(),
+            "Priority".to_string(),
+            "Todo".to_string(),
+            "Status".to_string(),
+            "Due".to_string(),
+        ],
     );
 
-    for column in &content_table {
-        for row in column {
-            execute!(stdout, Print(""), Print(row), Print(" "))?;
+    for (index, column) in content_table.iter().enumerate() {
+        // The first row holds the headers
+        let todo_index = index.checked_sub(1);
+        for (cell_index, row) in column.iter().enumerate() {
+            let cell = match (todo_index, cell_index) {
+                (Some(i), 2) => custom_widgets::color_priority(priorities[i], row),
+                (Some(i), 5) => custom_widgets::color_due(due_states[i], row),
+                _ => row.to_string(),
+            };
+            execute!(stdout, Print(""), Print(cell), Print(" "))?;
         }
         execute!(stdout, Print(" \n"))?;
     }
@@ -92,114 +232,488 @@ fn list_todo(stdout: &mut Stdout) -> io::Result<()> {
     Ok(())
 }
 
-fn create_todo(stdout: &mut Stdout) -> io::Result<()> {
+fn create_todo(stdout: &mut Stdout, args: &CreateTodoArgs) -> io::Result<()> {
+    let mut template = Todo::new("");
+    template.priority = args
+        .priority
+        .unwrap_or_else(|| config::get().default_priority());
+    for tag in &args.tags {
+        template.add_tag(tag);
+    }
+    template.due = args.due;
+    if let Some(query) = &args.project {
+        let project = projects_db::find_project(query)?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("'{}' is not a saved project", query),
+            )
+        })?;
+        template.project = Some(project.path);
+    } else {
+        template.project = current_project(args.global)?;
+    }
+
+    // Only ask for the text when it was not given in the command line
+    if let Some(text) = &args.text {
+        if text.trim().is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The todo cannot be empty",
+            ));
+        }
+        template.description = text.to_string();
+        template.add_description_tags();
+        let todo = todo_db::add_todo(template)?;
+        execute!(
+            stdout,
+            Print(ICON_CHECK.green()),
+            Print(format!("Todo {} added correctly!\n", todo.id))
+        )?;
+        return Ok(());
+    }
+
     print!("\x1B[2J\x1B[1;1H");
-    let mut input: Input<Rc<RefCell<(String, Option<String>)>>> = widgets::Input::new(
+    let mut input: Input<Rc<RefCell<String>>> = widgets::Input::new(
         IconAndLabel(ICON_QUESTION, "Type the todo: "),
         IconAndLabel(ICON_CHECK, "Type the todo: "),
     );
 
     input.after(|local, global_data| {
+        // The todo cannot be empty, a text of spaces is asked again
+        if local.complete_input && local.input.trim().is_empty() {
+            local.input.clear();
+            local.complete_input = false;
+            return Action::KeepSection;
+        }
         if local.complete_input {
-            let mut input_mutable = local.input.clone();
-            input_mutable.push_str(":0");
-            let exist_error = Todo::try_from(input_mutable.as_str()).is_err();
-            if exist_error {
-                (*global_data).borrow_mut().1 =
-                    Some("Could not create 'todo', maybe you wrote ':' in the input".to_string());
-            }
-
-            (*global_data).borrow_mut().0 = local.input.clone();
+            *(*global_data).borrow_mut() = local.input.clone();
             return Action::Next;
         }
         Action::KeepSection
     });
 
-    let mut text: TextBlock<Rc<RefCell<(String, Option<String>)>>> =
-        TextBlock::new("Adding todo...");
-    text.before(|local, global_state| {
-        if let Some(ref msg) = (*global_state).borrow().1 {
-            local.text = msg.to_string();
-            return RenderWidget::No;
-        }
+    let mut text: TextBlock<Rc<RefCell<String>>> = TextBlock::new("Adding todo...");
 
-        RenderWidget::Yes
+    text.after(move |local, global| {
+        let mut todo = template.clone();
+        todo.description = (*global).borrow().to_string();
+        todo.add_description_tags();
+
+        match todo_db::add_todo(todo) {
+            Ok(todo) => {
+                local
+                    .text
+                    .push_str(&format!("\nTodo {} added correctly!", todo.id));
+                Action::Next
+            }
+            Err(e) => {
+                local.text.push_str(&format!("\n{}", e));
+                Action::Exit
+            }
+        }
     });
 
-    text.after(|local, global| {
-        let mut program_path = utils::get_folder_program().unwrap();
-        program_path.push(constants::NAME_TODOS_FILE);
+    let mut render_view = SectionsView::new(String::new());
+    render_view.child(input);
+    render_view.child(text);
 
-        let todo_res = utils::get_content_file(&program_path);
-        let mut todo_content = match todo_res {
-            Ok(s) => s,
-            Err(e) => {
-                local.text.push_str(&format!("\n{}", e.to_string()));
-                return Action::Exit;
+    render_view.render(stdout)?;
+
+    Ok(())
+}
+
+/// Change the status of the todo matching `query`, without any interactive widget.
+fn set_todo_state(
+    stdout: &mut Stdout,
+    query: &str,
+    new_state: impl Fn(&TodoState) -> TodoState,
+) -> io::Result<()> {
+    let mut todos = todo_db::get_todos()?;
+    let todo = find_todo(&mut todos, query)
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e.to_string()))?;
+
+    let state = new_state(&todo.status);
+    todo.change_icon(state);
+    let message = format!("{} {} {}\n", todo.icon(), todo.id, todo.description);
+    todo_db::write_todos(&todos)?;
+
+    execute!(stdout, Print(message))?;
+    Ok(())
+}
+
+fn list_tags(stdout: &mut Stdout, global: bool) -> io::Result<()> {
+    let todos = get_scoped_todos(&current_project(global)?)?;
+    // Open and completed todos of each tag, sorted by name
+    let mut counts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for todo in &todos {
+        for tag in &todo.tags {
+            let count = counts.entry(tag).or_default();
+            if todo.is_open() {
+                count.0 += 1;
+            } else if todo.status == TodoState::Completed {
+                count.1 += 1;
             }
-        };
+        }
+    }
 
-        todo_content.push_str(&format!(
-            "\n{}:{}",
-            (*global).borrow().0.as_str(),
-            TodoState::NoStarted.to_i8()
-        ));
+    if counts.is_empty() {
+        execute!(stdout, Print("No todo has a tag.\n"))?;
+        return Ok(());
+    }
+    let rows: Vec<Vec<String>> = counts
+        .into_iter()
+        .map(|(tag, (open, completed))| {
+            vec![format!("#{}", tag), open.to_string(), completed.to_string()]
+        })
+        .collect();
+    let content_table = table(
+        rows,
+        vec![
+            "Tag".to_string(),
+            "Open".to_string(),
+            "Completed".to_string(),
+        ],
+    );
+    for row in content_table {
+        execute!(stdout, Print(format!("{}\n", row.join(" ").trim_end())))?;
+    }
+    Ok(())
+}
+
+/// Ask the new text of a todo, `None` when the edit is cancelled.
+fn ask_description(
+    stdout: &mut Stdout,
+    todo: &Todo,
+    with_editor: bool,
+) -> io::Result<Option<String>> {
+    if with_editor {
+        let (path, mut file) = utils::create_temp_file("wflow-todo")?;
+        let written = writeln!(file, "{}", todo.description);
+        // Closed before editing, some editors cannot open a file in use
+        drop(file);
+        let result = written
+            .and_then(|_| utils::edit_file(&path))
+            .and_then(|_| fs::read_to_string(&path));
+        let _ = fs::remove_file(&path);
+        let content = result?;
+        // The lines are joined, a todo is a single line
+        let text: Vec<&str> = content
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .collect();
+        return Ok(Some(text.join(" ")));
+    }
 
-        if let Err(e) = utils::write_file(program_path, &todo_content.trim()) {
-            local.text.push_str(&format!("\n{}", e.to_string()));
-            return Action::Exit;
+    let mut input: TextInput<Rc<RefCell<Option<String>>>> = TextInput::new(&todo.description);
+    input.add_text_init(ICON_QUESTION, "Edit the todo: ");
+    input.add_text_final(ICON_CHECK, "Todo: ");
+    input.after(|local, global_data| {
+        if local.is_selected {
+            if !local.is_cancelled {
+                *(*global_data).borrow_mut() = Some(local.text.clone());
+            }
+            return Action::Next;
         }
-        local.text.push_str("\nTodo added correctly!");
-        Action::Next
+        Action::KeepSection
     });
 
-    let mut render_view = SectionsView::new((String::new(), None));
+    let mut render_view = SectionsView::new(None);
     render_view.child(input);
-    render_view.child(text);
-
     render_view.render(stdout)?;
+    execute!(stdout, Print("\n"))?;
 
+    let text = render_view.global_state.borrow().clone();
+    Ok(text)
+}
+
+fn edit_todo(stdout: &mut Stdout, query: &str, with_editor: bool) -> io::Result<()> {
+    let mut todos = todo_db::get_todos()?;
+    let todo = find_todo(&mut todos, query)
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e.to_string()))?;
+
+    let text = match ask_description(stdout, todo, with_editor)? {
+        Some(text) => text,
+        None => return Ok(()),
+    };
+    if text.trim().is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The todo cannot be empty",
+        ));
+    }
+    todo.set_description(&text);
+    let message = format!("{} {} {}\n", todo.icon(), todo.id, todo.description);
+    todo_db::write_todos(&todos)?;
+
+    execute!(stdout, Print(message))?;
     Ok(())
 }
 
-fn change_todo(stdout: &mut Stdout) -> io::Result<()> {
-    let mut program_path = utils::get_folder_program()?;
-    program_path.push(constants::NAME_TODOS_FILE);
+fn delete_todos(stdout: &mut Stdout, queries: &[String]) -> io::Result<()> {
+    let mut todos = todo_db::get_todos()?;
+    // Find all of them first, so nothing is deleted when a query is wrong
+    let mut ids = Vec::new();
+    for query in queries {
+        let todo = find_todo(&mut todos, query)
+            .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e.to_string()))?;
+        if !ids.contains(&todo.id) {
+            ids.push(todo.id);
+        }
+    }
+
+    let (deleted, kept): (Vec<Todo>, Vec<Todo>) =
+        todos.into_iter().partition(|t| ids.contains(&t.id));
+    todo_db::write_todos(&kept)?;
 
-    let todo_content = utils::get_content_file(&program_path)?;
+    for todo in deleted {
+        execute!(
+            stdout,
+            Print(ICON_CHECK.green()),
+            Print(format!("Todo {} deleted: {}\n", todo.id, todo.description))
+        )?;
+    }
+    Ok(())
+}
 
-    if todo_content.trim().len() == 0 {
+fn list_archive(stdout: &mut Stdout, global: bool, since: Option<NaiveDate>) -> io::Result<()> {
+    let project = current_project(global)?;
+    let mut todos: Vec<Todo> = todo_db::get_archived_todos()?
+        .into_iter()
+        .filter(|t| project.is_none() || t.project == project)
+        .filter(|t| {
+            let completed = t.completed_at.map(|d| d.with_timezone(&Local).date_naive());
+            since.is_none_or(|since| completed.is_some_and(|c| c >= since))
+        })
+        .collect();
+    // The last completed first
+    todos.sort_by_key(|t| Reverse(t.completed_at));
+
+    if todos.is_empty() {
+        execute!(stdout, Print("No archived todo.\n"))?;
         return Ok(());
     }
-    let todos_collect: Vec<Todo> = todo_content
-        .trim()
-        .lines()
-        .map(|f| Todo::try_from(f).unwrap())
+    let rows: Vec<Vec<String>> = todos
+        .into_iter()
+        .map(|todo| {
+            let completed = todo
+                .completed_at
+                .map(|d| d.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string());
+            vec![
+                todo.id.to_string(),
+                completed.unwrap_or_default(),
+                todo.description,
+            ]
+        })
+        .collect();
+    let content_table = table(
+        rows,
+        vec![
+            "ID".to_string(),
+            "Completed".to_string(),
+            "Todo".to_string(),
+        ],
+    );
+    for row in content_table {
+        execute!(stdout, Print(format!("{}\n", row.join(" ").trim_end())))?;
+    }
+    Ok(())
+}
+
+fn restore_todo(stdout: &mut Stdout, id: u32) -> io::Result<()> {
+    let mut archived = todo_db::get_archived_todos()?;
+    let index = archived.iter().position(|t| t.id == id).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No archived todo has the id {}", id),
+        )
+    })?;
+    let todo = archived.remove(index);
+
+    // The todos are written first, a failure never loses a todo
+    let mut todos = todo_db::get_todos()?;
+    let restored = if todos.iter().any(|t| t.id == todo.id) {
+        // Another todo got the id, the restored one gets a new id
+        todo_db::add_todo(todo)?
+    } else {
+        todos.push(todo.clone());
+        todo_db::write_todos(&todos)?;
+        todo
+    };
+    todo_db::write_archived_todos(&archived)?;
+
+    let message = match restored.id == id {
+        true => format!("Todo {} restored: {}\n", id, restored.description),
+        false => format!(
+            "Todo {} restored as {}: {}\n",
+            id, restored.id, restored.description
+        ),
+    };
+    execute!(stdout, Print(ICON_CHECK.green()), Print(message))?;
+    Ok(())
+}
+
+fn show_agenda(stdout: &mut Stdout, global: bool) -> io::Result<()> {
+    let mut todos = get_scoped_todos(&current_project(global)?)?;
+    // By date, then by priority
+    sort_todos(&mut todos, TodoSort::Priority);
+    todos.sort_by_key(|t| t.due);
+
+    let today = Local::now().date_naive();
+    let mut is_empty = true;
+    for group in DueState::ALL {
+        let group_todos: Vec<&Todo> = todos
+            .iter()
+            .filter(|t| t.due_state(today) == Some(group))
+            .collect();
+        if group_todos.is_empty() {
+            continue;
+        }
+        is_empty = false;
+
+        let title = custom_widgets::color_due(Some(group), &group.to_string());
+        execute!(stdout, Print(format!("{}\n", title.bold())))?;
+        for todo in group_todos {
+            let due = todo.due.map(|d| d.format("%a %Y-%m-%d").to_string());
+            execute!(
+                stdout,
+                Print(format!(
+                    "  {} {} {} {}\n",
+                    custom_widgets::color_due(Some(group), &due.unwrap_or_default()),
+                    custom_widgets::color_priority(todo.priority, &todo.priority.to_string()),
+                    todo.id.to_string().dark_grey(),
+                    todo.description
+                ))
+            )?;
+        }
+    }
+
+    if is_empty {
+        execute!(stdout, Print("No open todo has a due date.\n"))?;
+    }
+    Ok(())
+}
+
+fn set_todo_priority(stdout: &mut Stdout, query: &str, priority: Priority) -> io::Result<()> {
+    let mut todos = todo_db::get_todos()?;
+    let todo = find_todo(&mut todos, query)
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e.to_string()))?;
+
+    todo.priority = priority;
+    let message = format!(
+        "{} {} {}\n",
+        custom_widgets::color_priority(priority, &priority.to_string()),
+        todo.id,
+        todo.description
+    );
+    todo_db::write_todos(&todos)?;
+
+    execute!(stdout, Print(message))?;
+    Ok(())
+}
+
+/// Todos changed in the `CheckList` of `todo check`.
+#[derive(Clone, Default)]
+struct ChangedTodos {
+    todos: Vec<Todo>,
+    deleted: Vec<u32>,
+    /// Error of the save, printed once the alternate screen is left.
+    error: Option<String>,
+}
+
+/// What to do with the completed todos once the `CheckList` is closed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CompletedAction {
+    Keep,
+    Archive,
+    Delete,
+}
+
+/// Save the todos changed in the `CheckList` back into the store.
+///
+/// The deleted todos are removed. Unless `action` is `Keep`, the changed
+/// todos that are completed are moved to the archive or deleted.
+fn save_changed_todos(changed: &ChangedTodos, action: CompletedAction) -> io::Result<()> {
+    let mut todos: Vec<Todo> = todo_db::get_todos()?
+        .into_iter()
+        .filter(|todo| !changed.deleted.contains(&todo.id))
+        .map(
+            |todo| match changed.todos.iter().find(|t| t.id == todo.id) {
+                Some(t) => t.clone(),
+                None => todo,
+            },
+        )
         .collect();
 
-    let todo_completed = Print(format!("{}: Completed", "[x]".cyan().bold()));
-    let todo_blank = Print(format!("{}: Not started", "[ ]".cyan().bold()));
-    let change_behavior = Print(format!("{}: Change the icon", "←/→".cyan().bold()));
+    if action != CompletedAction::Keep {
+        let is_changed_completed = |todo: &Todo| {
+            todo.status == TodoState::Completed && changed.todos.iter().any(|t| t.id == todo.id)
+        };
+        if action == CompletedAction::Archive {
+            // The archive is written first, a failure never loses a todo
+            let completed: Vec<Todo> = todos
+                .iter()
+                .filter(|t| is_changed_completed(t))
+                .cloned()
+                .collect();
+            todo_db::archive_todos(&completed)?;
+        }
+        todos.retain(|t| !is_changed_completed(t));
+    }
+    todo_db::write_todos(&todos)
+}
+
+fn change_todo(stdout: &mut Stdout, global: bool) -> io::Result<()> {
+    let mut todos_collect = get_scoped_todos(&current_project(global)?)?;
+    sort_todos(&mut todos_collect, TodoSort::default());
+
+    if todos_collect.is_empty() {
+        return Ok(());
+    }
+
+    let states_legend: Vec<String> = TodoState::ALL
+        .iter()
+        .map(|state| format!("{}: {}", state.icon().cyan().bold(), state))
+        .collect();
+    let change_behavior = Print(format!("{}: Change the state", "←/→".cyan().bold()));
+    let filter_behavior = Print(format!("{}: Filter", "Type".cyan().bold()));
+    let edit_behavior = Print(format!(
+        "{}: Edit, {}: Delete",
+        "Ctrl+E".cyan().bold(),
+        "Del".cyan().bold()
+    ));
 
     let legend_text = TextBlock::new(&format!(
-        "{} - {}, {}",
-        todo_completed, todo_blank, change_behavior
+        "{}, {}, {}, {}",
+        states_legend.join(" - "),
+        change_behavior,
+        filter_behavior,
+        edit_behavior
     ));
 
-    let mut render_todos: custom_widgets::CheckList<Rc<RefCell<Vec<Todo>>>> =
+    let mut render_todos: custom_widgets::CheckList<Rc<RefCell<ChangedTodos>>> =
         custom_widgets::CheckList::new(todos_collect);
 
     render_todos.after(|local_state, global_state| {
         if local_state.is_selected {
-            *(*global_state).borrow_mut() = local_state.todos.clone();
+            let changed = ChangedTodos {
+                todos: local_state.todos.clone(),
+                deleted: local_state.deleted.clone(),
+                error: None,
+            };
+            *(*global_state).borrow_mut() = changed.clone();
             let todos = &*(*global_state)
                 .borrow()
+                .todos
                 .iter()
                 .filter(|t| t.status == TodoState::Completed)
-                .map(|t| t.clone())
+                .cloned()
                 .collect::<Vec<Todo>>();
 
-            if todos.len() == 0 {
+            if todos.is_empty() {
+                if let Err(e) = save_changed_todos(&changed, CompletedAction::Keep) {
+                    global_state.borrow_mut().error = Some(e.to_string());
+                }
                 return Action::Exit;
             }
 
@@ -208,28 +722,21 @@ fn change_todo(stdout: &mut Stdout) -> io::Result<()> {
         Action::KeepSection
     });
 
-    let mut confirmation: widgets::ListSelected<Rc<RefCell<Vec<Todo>>>> =
-        widgets::ListSelected::new(vec!["Yes", "No"]);
+    let mut confirmation: widgets::ListSelected<Rc<RefCell<ChangedTodos>>> =
+        widgets::ListSelected::new(vec!["Archive", "Delete", "Keep"]);
 
-    confirmation.add_text_init(ICON_QUESTION, "Delete the completed todos?: ");
-    confirmation.after(move |state, global_state| {
+    confirmation.add_text_init(ICON_QUESTION, "Archive the completed todos?: ");
+    confirmation.after(|state, global_state| {
         if state.is_selected {
-            let todos = &*(*global_state).borrow();
-
-            let mut content = String::new();
-
-            let delete_todo = state.offset != state.length - 1; // SI selection!
+            let action = match state.offset {
+                0 => CompletedAction::Archive,
+                1 => CompletedAction::Delete,
+                _ => CompletedAction::Keep,
+            };
 
-            for todo in todos {
-                if delete_todo && todo.status == TodoState::Completed {
-                    continue;
-                }
-                content.push_str(&format!("{}:{}\n", todo.description, todo.status.to_i8()));
-            }
-
-            let res = utils::write_file(&program_path, content.trim());
+            let res = save_changed_todos(&global_state.borrow(), action);
             if let Err(e) = res {
-                println!("{}", format!("{}", e.to_string()));
+                global_state.borrow_mut().error = Some(e.to_string());
                 return Action::Exit;
             }
 
@@ -239,11 +746,15 @@ fn change_todo(stdout: &mut Stdout) -> io::Result<()> {
         Action::KeepSection
     });
 
-    let mut section_view = SectionsView::new(vec![]);
+    let mut section_view = SectionsView::new(ChangedTodos::default());
     section_view.child(legend_text);
     section_view.child(render_todos);
     section_view.child(confirmation);
     section_view.render(stdout)?;
 
+    // Nothing was saved, the changes are lost unless the error is seen
+    if let Some(e) = section_view.global_state.borrow().error.clone() {
+        return Err(io::Error::other(e));
+    }
     Ok(())
 }
@@ -1,86 +1,468 @@
-#[derive(Clone)]
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc, Weekday};
+use clap::ValueEnum;
+use crossterm::style::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Todo {
+    /// Stable identifier, assigned by `todo_db` when the todo is saved.
+    #[serde(default)]
+    pub id: u32,
     pub description: String,
     pub status: TodoState,
-    pub icon: String,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub due: Option<NaiveDate>,
+    /// Path of the `tidy` project this todo belongs to.
+    #[serde(default)]
+    pub project: Option<String>,
+    /// When the todo was completed, kept in the archive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Utc>>,
 }
 
+/// Parse a line of the legacy `todos.txt` format: `description:status`.
+///
+/// The status is taken from the last `:`, so descriptions containing
+/// colons are kept intact.
 impl TryFrom<&str> for Todo {
     type Error = TodoError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let parts: Vec<&str> = value.trim().split(":").collect();
-
-        if parts.len() < 2 {
-            return Err(TodoError::MissingData);
-        }
-        let description = parts[0].to_string();
+        let (description, status) = match value.trim().rsplit_once(':') {
+            Some(parts) => parts,
+            None => return Err(TodoError::MissingData),
+        };
 
-        let status = match parts[1].parse::<i8>() {
-            Ok(n) => TodoState::from(n),
+        let status = match status.parse::<i8>() {
+            Ok(n) => TodoState::try_from(n)?,
             Err(_) => return Err(TodoError::ParseError),
         };
 
-        let icon = match status {
-            TodoState::Completed => format!("[x]"),
-            TodoState::NoStarted => format!("[ ]"),
-        };
+        let mut todo = Todo::new(description);
+        todo.status = status;
+        Ok(todo)
+    }
+}
 
-        return Ok(Self {
-            description,
-            status,
-            icon,
-        });
+/// Find the todo referenced by `query`.
+///
+/// The query is first tried as an id, then as a unique prefix of a description.
+/// An empty query matches no todo.
+pub fn find_todo<'a>(todos: &'a mut [Todo], query: &str) -> Result<&'a mut Todo, TodoError> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err(TodoError::NotFound(query.to_string()));
+    }
+    if let Ok(id) = query.parse::<u32>() {
+        if let Some(index) = todos.iter().position(|t| t.id == id) {
+            return Ok(&mut todos[index]);
+        }
+    }
+
+    let matches: Vec<usize> = todos
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.description.starts_with(query))
+        .map(|(i, _)| i)
+        .collect();
+
+    match matches.as_slice() {
+        [] => Err(TodoError::NotFound(query.to_string())),
+        [index] => Ok(&mut todos[*index]),
+        _ => Err(TodoError::Ambiguous(
+            query.to_string(),
+            matches.iter().map(|i| todos[*i].id).collect(),
+        )),
     }
 }
 
 impl Todo {
     pub fn new(desc: &str) -> Self {
         Self {
+            id: 0,
             description: desc.to_string(),
             status: TodoState::NoStarted,
-            icon: String::from("[ ]"),
+            priority: Priority::default(),
+            tags: Vec::new(),
+            due: None,
+            project: None,
+            completed_at: None,
         }
     }
+    pub fn icon(&self) -> &'static str {
+        self.status.icon()
+    }
+    /// Whether the todo still needs some work.
+    pub fn is_open(&self) -> bool {
+        !matches!(self.status, TodoState::Completed | TodoState::Cancelled)
+    }
     pub fn change_icon(&mut self, new_state: TodoState) {
-        self.icon = match new_state {
-            TodoState::Completed => format!("[x]"),
-            TodoState::NoStarted => format!("[ ]"),
-        };
+        if new_state != TodoState::Completed {
+            self.completed_at = None;
+        } else if self.status != TodoState::Completed {
+            self.completed_at = Some(Utc::now());
+        }
         self.status = new_state
     }
+
+    /// Add `tag` if the todo does not have it yet, without the leading `#`.
+    pub fn add_tag(&mut self, tag: &str) {
+        let tag = normalize_tag(tag);
+        if !tag.is_empty() && !self.tags.contains(&tag) {
+            self.tags.push(tag);
+        }
+    }
+
+    /// Add the `#tags` written in the description, which is kept as it is.
+    pub fn add_description_tags(&mut self) {
+        for tag in parse_tags(&self.description) {
+            self.add_tag(&tag);
+        }
+    }
+
+    /// Change the description, and the tags that come from its `#tags`.
+    pub fn set_description(&mut self, text: &str) {
+        let new_tags = parse_tags(text);
+        for tag in parse_tags(&self.description) {
+            if !new_tags.contains(&tag) {
+                self.tags.retain(|t| *t != tag);
+            }
+        }
+        self.description = text.trim().to_string();
+        self.add_description_tags();
+    }
+
+    /// Whether the todo has `tag`, compared like the tags are added.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = normalize_tag(tag);
+        self.tags.iter().any(|t| t.to_lowercase() == tag)
+    }
+
+    /// How close the due date of an open todo is, `None` when there is nothing to do.
+    pub fn due_state(&self, today: NaiveDate) -> Option<DueState> {
+        let due = self.due.filter(|_| self.is_open())?;
+        let days = (due - today).num_days();
+        let state = match days {
+            ..=-1 => DueState::Overdue,
+            0 => DueState::Today,
+            1..=7 => DueState::ThisWeek,
+            _ => DueState::Later,
+        };
+        Some(state)
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// A tag as it is stored: trimmed, lowercase and without the leading `#`.
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+/// Find the `#tags` of a text.
+///
+/// A tag starts with a letter and goes on with letters, digits, `-` and `_`,
+/// so `#1` or `C#` are not tags.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .filter_map(|word| word.strip_prefix('#'))
+        .map(|word| {
+            let end = word
+                .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+                .unwrap_or(word.len());
+            &word[..end]
+        })
+        .filter(|tag| tag.starts_with(char::is_alphabetic))
+        .map(|tag| tag.to_lowercase())
+        .collect()
+}
+
+/// Tags a todo must have, and the ones it must not have, given as `tag` and `!tag`.
+///
+/// The tags are normalized like `Todo::add_tag` does, the empty ones are ignored.
+#[derive(Debug, Default)]
+pub struct TagFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl TagFilter {
+    pub fn new(tags: &[String]) -> Self {
+        let mut filter = Self::default();
+        for tag in tags {
+            let (list, tag) = match tag.trim().strip_prefix('!') {
+                Some(tag) => (&mut filter.exclude, tag),
+                None => (&mut filter.include, tag.as_str()),
+            };
+            let tag = normalize_tag(tag);
+            if !tag.is_empty() && !list.contains(&tag) {
+                list.push(tag);
+            }
+        }
+        filter
+    }
+
+    /// Whether `todo` has all the included tags and none of the excluded ones.
+    pub fn matches(&self, todo: &Todo) -> bool {
+        self.include.iter().all(|t| todo.has_tag(t))
+            && !self.exclude.iter().any(|t| todo.has_tag(t))
+    }
+}
+
+/// Groups of the todos in `todo agenda`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DueState {
+    Overdue,
+    Today,
+    /// In the next seven days
+    ThisWeek,
+    Later,
+}
+
+impl DueState {
+    pub const ALL: [DueState; 4] = [
+        DueState::Overdue,
+        DueState::Today,
+        DueState::ThisWeek,
+        DueState::Later,
+    ];
+
+    /// Colour of the due date in the lists, `None` keeps the default one.
+    pub fn color(&self) -> Option<Color> {
+        match self {
+            DueState::Overdue => Some(Color::Red),
+            DueState::Today => Some(Color::Yellow),
+            DueState::ThisWeek | DueState::Later => None,
+        }
+    }
+}
+
+impl Display for DueState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DueState::Overdue => write!(f, "Overdue"),
+            DueState::Today => write!(f, "Today"),
+            DueState::ThisWeek => write!(f, "This week"),
+            DueState::Later => write!(f, "Later"),
+        }
+    }
+}
+
+/// Parse a due date relative to `today`.
+///
+/// Accepts ISO dates like `2024-05-31`, `today`, `tomorrow`, offsets like
+/// `+3d` or `+2w`, and days of the week like `fri`, meaning the next one.
+pub fn parse_due_date(text: &str, today: NaiveDate) -> Result<NaiveDate, TodoError> {
+    let text = text.trim().to_lowercase();
+    let invalid = || TodoError::InvalidDate(text.clone());
+
+    if let Ok(date) = NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+        return Ok(date);
+    }
+    let days = match text.as_str() {
+        "today" => 0,
+        "tomorrow" => 1,
+        _ => match (text.strip_prefix('+'), text.parse::<Weekday>()) {
+            (Some(offset), _) => parse_days(offset).ok_or_else(invalid)?,
+            (None, Ok(weekday)) => {
+                let days = (7 + weekday.num_days_from_monday()
+                    - today.weekday().num_days_from_monday())
+                    % 7;
+                if days == 0 {
+                    7
+                } else {
+                    u64::from(days)
+                }
+            }
+            (None, Err(_)) => return Err(invalid()),
+        },
+    };
+    today.checked_add_days(Days::new(days)).ok_or_else(invalid)
+}
+
+/// Parse a number of days like `3d`, or of weeks like `2w`.
+fn parse_days(text: &str) -> Option<u64> {
+    let (number, days_per_unit) = match (text.strip_suffix('d'), text.strip_suffix('w')) {
+        (Some(number), _) => (number, 1),
+        (_, Some(number)) => (number, 7),
+        _ => return None,
+    };
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    number.parse::<u64>().ok()?.checked_mul(days_per_unit)
+}
+
+/// Parse a date in the past relative to `today`.
+///
+/// Accepts ISO dates like `2024-05-31`, `today`, `yesterday`, and durations
+/// like `3d` or `2w`, meaning that long ago.
+pub fn parse_since_date(text: &str, today: NaiveDate) -> Result<NaiveDate, TodoError> {
+    let text = text.trim().to_lowercase();
+    let invalid = || TodoError::InvalidSinceDate(text.clone());
+
+    if let Ok(date) = NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+        return Ok(date);
+    }
+    let days = match text.as_str() {
+        "today" => 0,
+        "yesterday" => 1,
+        _ => parse_days(&text).ok_or_else(invalid)?,
+    };
+    today.checked_sub_days(Days::new(days)).ok_or_else(invalid)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TodoState {
     Completed,
+    #[serde(rename = "not_started")]
     NoStarted,
+    InProgress,
+    Blocked,
+    Cancelled,
 }
 
-impl From<i8> for TodoState {
-    fn from(n: i8) -> Self {
+impl TryFrom<i8> for TodoState {
+    type Error = TodoError;
+
+    fn try_from(n: i8) -> Result<Self, Self::Error> {
         match n {
-            0 => TodoState::NoStarted,
-            1 => TodoState::Completed,
-            _ => TodoState::NoStarted,
+            0 => Ok(TodoState::NoStarted),
+            1 => Ok(TodoState::Completed),
+            2 => Ok(TodoState::InProgress),
+            3 => Ok(TodoState::Blocked),
+            4 => Ok(TodoState::Cancelled),
+            _ => Err(TodoError::UnknownState(n.to_string())),
         }
     }
 }
 
 impl TodoState {
-    pub fn to_i8(&self) -> i8 {
+    /// All the states, in the order they are cycled in the `CheckList`.
+    pub const ALL: [TodoState; 5] = [
+        TodoState::NoStarted,
+        TodoState::InProgress,
+        TodoState::Blocked,
+        TodoState::Completed,
+        TodoState::Cancelled,
+    ];
+
+    pub fn icon(&self) -> &'static str {
+        config::get().icon(self)
+    }
+
+    /// Name of the state in the todos file and the `--format` output.
+    pub fn key(&self) -> &'static str {
         match self {
-            TodoState::Completed => 1,
-            TodoState::NoStarted => 0,
+            TodoState::Completed => "completed",
+            TodoState::NoStarted => "not_started",
+            TodoState::InProgress => "in_progress",
+            TodoState::Blocked => "blocked",
+            TodoState::Cancelled => "cancelled",
         }
     }
+
+    pub fn next(&self) -> TodoState {
+        let index = Self::ALL.iter().position(|s| s == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()].clone()
+    }
+
+    pub fn prev(&self) -> TodoState {
+        let index = Self::ALL.iter().position(|s| s == self).unwrap_or(0);
+        Self::ALL[(index + Self::ALL.len() - 1) % Self::ALL.len()].clone()
+    }
 }
 
-impl ToString for TodoState {
-    fn to_string(&self) -> String {
+impl Display for TodoState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            TodoState::Completed => String::from("Completed"),
-            TodoState::NoStarted => String::from("Not started"),
+            TodoState::Completed => write!(f, "Completed"),
+            TodoState::NoStarted => write!(f, "Not started"),
+            TodoState::InProgress => write!(f, "In progress"),
+            TodoState::Blocked => write!(f, "Blocked"),
+            TodoState::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+/// Priority of a todo, `P0` is the most urgent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Priority {
+    P0,
+    P1,
+    #[default]
+    P2,
+    P3,
+}
+
+impl FromStr for Priority {
+    type Err = TodoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "p0" | "critical" => Ok(Priority::P0),
+            "p1" | "high" => Ok(Priority::P1),
+            "p2" | "medium" => Ok(Priority::P2),
+            "p3" | "low" => Ok(Priority::P3),
+            _ => Err(TodoError::InvalidPriority(s.to_string())),
+        }
+    }
+}
+
+impl Priority {
+    /// Colour of the priority in the lists, `None` keeps the default one.
+    pub fn color(&self) -> Option<Color> {
+        match self {
+            Priority::P0 => Some(Color::Red),
+            Priority::P1 => Some(Color::Yellow),
+            Priority::P2 => None,
+            Priority::P3 => Some(Color::DarkGrey),
+        }
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Priority::P0 => write!(f, "P0"),
+            Priority::P1 => write!(f, "P1"),
+            Priority::P2 => write!(f, "P2"),
+            Priority::P3 => write!(f, "P3"),
+        }
+    }
+}
+
+/// Order of the todos in `todo list` and `todo check`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TodoSort {
+    /// The most urgent first
+    #[default]
+    Priority,
+    /// The oldest first
+    Created,
+    /// In the order the states are cycled
+    Status,
+}
+
+///Sort `todos` by `sort`, the oldest first when they compare equal.
+pub fn sort_todos(todos: &mut [Todo], sort: TodoSort) {
+    todos.sort_by_key(|t| t.id);
+    match sort {
+        TodoSort::Priority => todos.sort_by_key(|t| t.priority),
+        TodoSort::Created => {}
+        TodoSort::Status => {
+            todos.sort_by_key(|t| TodoState::ALL.iter().position(|s| *s == t.status))
         }
     }
 }
@@ -89,13 +471,270 @@ impl ToString for TodoState {
 pub enum TodoError {
     ParseError,
     MissingData,
+    NotFound(String),
+    Ambiguous(String, Vec<u32>),
+    InvalidPriority(String),
+    InvalidDate(String),
+    InvalidSinceDate(String),
+    UnknownState(String),
 }
 
-impl ToString for TodoError {
-    fn to_string(&self) -> String {
+impl Display for TodoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            TodoError::ParseError => "Cannot convert Todo".to_string(),
-            TodoError::MissingData => "Missing data of Todo".to_string(),
+            TodoError::ParseError => write!(f, "Cannot convert Todo"),
+            TodoError::MissingData => write!(f, "Missing data of Todo"),
+            TodoError::InvalidPriority(p) => write!(
+                f,
+                "Invalid priority '{}', expected p0-p3 or critical/high/medium/low",
+                p
+            ),
+            TodoError::InvalidDate(date) => write!(
+                f,
+                "Invalid date '{}', expected YYYY-MM-DD, today, tomorrow, +3d, +2w or a day like fri",
+                date
+            ),
+            TodoError::InvalidSinceDate(date) => write!(
+                f,
+                "Invalid date '{}', expected YYYY-MM-DD, today, yesterday, 3d or 2w",
+                date
+            ),
+            TodoError::UnknownState(state) => write!(f, "Unknown todo state '{}'", state),
+            TodoError::NotFound(query) => write!(f, "No todo matches '{}'", query),
+            TodoError::Ambiguous(query, ids) => {
+                let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+                write!(
+                    f,
+                    "'{}' matches several todos ({}), use the id instead",
+                    query,
+                    ids.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl Error for TodoError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: u32, priority: Priority, status: TodoState) -> Todo {
+        let mut todo = Todo::new(&format!("todo {}", id));
+        todo.id = id;
+        todo.priority = priority;
+        todo.status = status;
+        todo
+    }
+
+    #[test]
+    fn todos_are_found_by_id_or_prefix() {
+        let mut todos = vec![
+            todo(1, Priority::P2, TodoState::NoStarted),
+            todo(12, Priority::P2, TodoState::NoStarted),
+        ];
+        todos[0].description = "Buy milk".to_string();
+        todos[1].description = "Buy bread".to_string();
+        assert_eq!(find_todo(&mut todos, " 12 ").unwrap().id, 12);
+        assert_eq!(find_todo(&mut todos, "Buy m").unwrap().id, 1);
+        assert!(matches!(
+            find_todo(&mut todos, "Buy"),
+            Err(TodoError::Ambiguous(_, ids)) if ids == vec![1, 12]
+        ));
+        assert!(matches!(
+            find_todo(&mut todos, "Sell"),
+            Err(TodoError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn empty_queries_find_no_todo() {
+        let mut todos = vec![todo(1, Priority::P2, TodoState::NoStarted)];
+        for query in ["", " ", "\t\n"] {
+            assert!(matches!(
+                find_todo(&mut todos, query),
+                Err(TodoError::NotFound(_))
+            ));
         }
     }
+
+    fn ids(todos: &[Todo]) -> Vec<u32> {
+        todos.iter().map(|t| t.id).collect()
+    }
+
+    fn sample() -> Vec<Todo> {
+        vec![
+            todo(3, Priority::P2, TodoState::Completed),
+            todo(1, Priority::P3, TodoState::NoStarted),
+            todo(4, Priority::P0, TodoState::Blocked),
+            todo(2, Priority::P2, TodoState::InProgress),
+        ]
+    }
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn tags_are_found_in_text() {
+        assert_eq!(
+            parse_tags("Fix #Bug in #front-end, #v2_api #1 C# ##x #Éclair"),
+            vec!["bug", "front-end", "v2_api", "éclair"]
+        );
+        assert!(parse_tags("no tags # here").is_empty());
+    }
+
+    #[test]
+    fn tags_are_normalized() {
+        let mut todo = Todo::new("todo");
+        todo.add_tag(" #Work ");
+        todo.add_tag("work");
+        todo.add_tag("#");
+        todo.add_tag("ÉTÉ");
+        assert_eq!(todo.tags, tags(&["work", "été"]));
+        assert!(todo.has_tag("#WORK"));
+        assert!(todo.has_tag("Été"));
+        assert!(!todo.has_tag("home"));
+    }
+
+    #[test]
+    fn tag_filter_includes_and_excludes() {
+        let mut todo = Todo::new("todo");
+        todo.add_tag("work");
+        todo.add_tag("été");
+
+        let filter = TagFilter::new(&tags(&["#Work", " ÉTÉ "]));
+        assert_eq!(filter.include, tags(&["work", "été"]));
+        assert!(filter.matches(&todo));
+        assert!(!TagFilter::new(&tags(&["work", "home"])).matches(&todo));
+        assert!(!TagFilter::new(&tags(&["!#Été"])).matches(&todo));
+        assert!(TagFilter::new(&tags(&["work", "!home"])).matches(&todo));
+    }
+
+    #[test]
+    fn empty_tags_are_ignored_by_the_filter() {
+        let filter = TagFilter::new(&tags(&["", " ", "!", "#", "!#"]));
+        assert!(filter.include.is_empty());
+        assert!(filter.exclude.is_empty());
+        assert!(filter.matches(&Todo::new("todo")));
+    }
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn due_dates_are_relative_to_today() {
+        // A wednesday
+        let today = date("2024-05-15");
+        assert_eq!(
+            parse_due_date("2024-06-01", today).unwrap(),
+            date("2024-06-01")
+        );
+        assert_eq!(parse_due_date("today", today).unwrap(), today);
+        assert_eq!(
+            parse_due_date(" Tomorrow ", today).unwrap(),
+            date("2024-05-16")
+        );
+        assert_eq!(parse_due_date("+0d", today).unwrap(), today);
+        assert_eq!(parse_due_date("+3d", today).unwrap(), date("2024-05-18"));
+        assert_eq!(parse_due_date("+2w", today).unwrap(), date("2024-05-29"));
+    }
+
+    #[test]
+    fn due_weekdays_are_the_next_ones() {
+        let today = date("2024-05-15");
+        assert_eq!(parse_due_date("fri", today).unwrap(), date("2024-05-17"));
+        assert_eq!(parse_due_date("monday", today).unwrap(), date("2024-05-20"));
+        assert_eq!(parse_due_date("wed", today).unwrap(), date("2024-05-22"));
+        let sunday = date("2024-05-19");
+        assert_eq!(parse_due_date("mon", sunday).unwrap(), date("2024-05-20"));
+    }
+
+    #[test]
+    fn wrong_due_dates_are_rejected() {
+        let today = date("2024-05-15");
+        for text in [
+            "",
+            "+",
+            "+d",
+            "3d",
+            "+3x",
+            "+-1d",
+            "+3é",
+            "fré",
+            "é",
+            "2024-13-01",
+        ] {
+            assert!(parse_due_date(text, today).is_err(), "{}", text);
+        }
+        assert!(parse_due_date("+99999999999999d", today).is_err());
+        assert!(parse_due_date("+99999999999999999999w", today).is_err());
+        assert!(parse_due_date("+3074457345618258603w", today).is_err());
+    }
+
+    #[test]
+    fn since_dates_are_in_the_past() {
+        let today = date("2024-05-15");
+        assert_eq!(
+            parse_since_date("2024-01-01", today).unwrap(),
+            date("2024-01-01")
+        );
+        assert_eq!(parse_since_date("today", today).unwrap(), today);
+        assert_eq!(
+            parse_since_date("yesterday", today).unwrap(),
+            date("2024-05-14")
+        );
+        assert_eq!(parse_since_date("0d", today).unwrap(), today);
+        assert_eq!(parse_since_date("3d", today).unwrap(), date("2024-05-12"));
+        assert_eq!(parse_since_date("2W", today).unwrap(), date("2024-05-01"));
+        for text in ["", "d", "+3d", "3", "3é", "é", "99999999999999d"] {
+            assert!(parse_since_date(text, today).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn due_state_of_open_todos() {
+        let today = date("2024-05-15");
+        let mut todo = Todo::new("todo");
+        assert_eq!(todo.due_state(today), None);
+        let states = [
+            ("2024-05-14", DueState::Overdue),
+            ("2024-05-15", DueState::Today),
+            ("2024-05-16", DueState::ThisWeek),
+            ("2024-05-22", DueState::ThisWeek),
+            ("2024-05-23", DueState::Later),
+        ];
+        for (due, state) in states {
+            todo.due = Some(date(due));
+            assert_eq!(todo.due_state(today), Some(state), "{}", due);
+        }
+        todo.due = Some(date("2024-05-14"));
+        todo.status = TodoState::Completed;
+        assert_eq!(todo.due_state(today), None);
+        todo.status = TodoState::Cancelled;
+        assert_eq!(todo.due_state(today), None);
+    }
+
+    #[test]
+    fn sort_by_priority_keeps_the_oldest_first() {
+        let mut todos = sample();
+        sort_todos(&mut todos, TodoSort::Priority);
+        assert_eq!(ids(&todos), vec![4, 2, 3, 1]);
+    }
+
+    #[test]
+    fn sort_by_created_uses_the_ids() {
+        let mut todos = sample();
+        sort_todos(&mut todos, TodoSort::Created);
+        assert_eq!(ids(&todos), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn sort_by_status_follows_the_cycle() {
+        let mut todos = sample();
+        sort_todos(&mut todos, TodoSort::Status);
+        assert_eq!(ids(&todos), vec![1, 2, 4, 3]);
+    }
 }
@@ -0,0 +1,365 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::{config, constants, utils};
+
+use super::todo::{Todo, TodoError};
+
+/// Version of the schema written to the todos file.
+///
+/// Bump it whenever fields are added to `TodoStore` or `Todo`: older
+/// binaries don't know them and would drop them on their next write.
+///
+/// - 1: todos without ids
+/// - 2: stable ids and `next_id`
+/// - 3: priority, tags, due date and project of the todos
+/// - 4: completion date of the todos, and the archive file
+const SCHEMA_VERSION: u32 = 4;
+
+#[derive(Serialize, Deserialize)]
+struct TodoStore {
+    version: u32,
+    /// Next id to hand out, ids are never reused.
+    #[serde(default)]
+    next_id: u32,
+    todos: Vec<Todo>,
+}
+
+impl TodoStore {
+    /// Bring a store of an older schema version up to `SCHEMA_VERSION`.
+    ///
+    /// The fields missing in the older versions get the defaults of `Todo`
+    /// when the file is read, only the ids must be given here.
+    fn migrate(&mut self) {
+        self.assign_ids();
+        self.version = SCHEMA_VERSION;
+    }
+
+    /// Never hand out the ids of `archived`, restoring them would collide.
+    fn reserve_ids(&mut self, archived: &[Todo]) {
+        let max_id = archived.iter().map(|t| t.id).max().unwrap_or(0);
+        self.next_id = self.next_id.max(max_id + 1);
+    }
+
+    /// Give an id to the todos that don't have one yet (schema version 1).
+    fn assign_ids(&mut self) {
+        let max_id = self.todos.iter().map(|t| t.id).max().unwrap_or(0);
+        self.next_id = self.next_id.max(max_id + 1);
+        for todo in &mut self.todos {
+            if todo.id == 0 {
+                todo.id = self.next_id;
+                self.next_id += 1;
+            }
+        }
+    }
+}
+
+fn get_todos_path() -> io::Result<PathBuf> {
+    if let Some(path) = config::get().todos_file() {
+        return Ok(path);
+    }
+    let mut path = utils::get_data_folder()?;
+    path.push(constants::NAME_TODOS_FILE);
+    Ok(path)
+}
+
+fn invalid_data<E: ToString>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+/// Refuse a file written by a newer wflow, saving it again would drop
+/// the fields this one does not know.
+fn check_version(path: &Path, version: u32) -> io::Result<()> {
+    if version > SCHEMA_VERSION {
+        return Err(invalid_data(format!(
+            "{} uses schema version {}, but this wflow only supports up to {}",
+            path.display(),
+            version,
+            SCHEMA_VERSION
+        )));
+    }
+    Ok(())
+}
+
+fn read_store() -> io::Result<TodoStore> {
+    let path = get_todos_path()?;
+    if !path.exists() {
+        return migrate_legacy_todos();
+    }
+
+    let content = fs::read_to_string(&path)?;
+    if content.trim().is_empty() {
+        return Ok(TodoStore {
+            version: SCHEMA_VERSION,
+            next_id: 1,
+            todos: Vec::new(),
+        });
+    }
+
+    let mut store: TodoStore = serde_json::from_str(&content)
+        .map_err(|e| invalid_data(format!("Cannot read {}: {}", path.display(), e)))?;
+
+    check_version(&path, store.version)?;
+    if store.version < SCHEMA_VERSION {
+        // Older binaries refuse the migrated file, keep a copy they can read
+        let backup = path.with_extension(format!("json.v{}.bak", store.version));
+        if !backup.exists() {
+            fs::copy(&path, &backup)?;
+        }
+    }
+    store.migrate();
+    Ok(store)
+}
+
+fn write_store(store: &TodoStore) -> io::Result<()> {
+    let content = serde_json::to_string_pretty(store).map_err(invalid_data)?;
+
+    // Write to a temporary file first, so a crash never leaves a truncated store.
+    let path = get_todos_path()?;
+    let tmp_path = path.with_extension("json.tmp");
+    utils::write_file(&tmp_path, &content)?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+///Get all the saved todos.
+///
+/// If the store does not exist yet, the legacy `todos.txt` is migrated first.
+pub fn get_todos() -> io::Result<Vec<Todo>> {
+    Ok(read_store()?.todos)
+}
+
+///Replace the saved todos, keeping the id counter.
+pub fn write_todos(todos: &[Todo]) -> io::Result<()> {
+    let mut store = read_store()?;
+    store.version = SCHEMA_VERSION;
+    store.todos = todos.to_vec();
+    store.reserve_ids(&get_archived_todos()?);
+    store.assign_ids();
+    write_store(&store)
+}
+
+///Save a new todo and return it with its assigned id.
+pub fn add_todo(mut todo: Todo) -> io::Result<Todo> {
+    let mut store = read_store()?;
+    store.version = SCHEMA_VERSION;
+    store.reserve_ids(&get_archived_todos()?);
+    todo.id = store.next_id;
+    store.next_id += 1;
+    store.todos.push(todo.clone());
+    write_store(&store)?;
+    Ok(todo)
+}
+
+/// Completed todos moved out of the todos file, with the same ids.
+#[derive(Serialize, Deserialize)]
+struct ArchiveStore {
+    version: u32,
+    todos: Vec<Todo>,
+}
+
+fn get_archive_path() -> io::Result<PathBuf> {
+    Ok(archive_path_for(&get_todos_path()?))
+}
+
+/// Archive of the todos file at `todos_path`, like `todos_archive.json` for
+/// `todos.json`, so the todos files of a folder don't share it.
+fn archive_path_for(todos_path: &Path) -> PathBuf {
+    let stem = todos_path
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    todos_path.with_file_name(format!("{}{}.json", stem, constants::ARCHIVE_FILE_SUFFIX))
+}
+
+///Get all the archived todos.
+pub fn get_archived_todos() -> io::Result<Vec<Todo>> {
+    let path = get_archive_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path)?;
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let store: ArchiveStore = serde_json::from_str(&content)
+        .map_err(|e| invalid_data(format!("Cannot read {}: {}", path.display(), e)))?;
+    check_version(&path, store.version)?;
+    Ok(store.todos)
+}
+
+///Replace the archived todos.
+pub fn write_archived_todos(todos: &[Todo]) -> io::Result<()> {
+    let store = ArchiveStore {
+        version: SCHEMA_VERSION,
+        todos: todos.to_vec(),
+    };
+    let content = serde_json::to_string_pretty(&store).map_err(invalid_data)?;
+
+    let path = get_archive_path()?;
+    let tmp_path = path.with_extension("json.tmp");
+    utils::write_file(&tmp_path, &content)?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+///Add todos to the archive, they must be removed from the todos by the caller.
+///
+/// The todos without completion date get the current one.
+pub fn archive_todos(todos: &[Todo]) -> io::Result<()> {
+    let mut archived = get_archived_todos()?;
+    for todo in todos {
+        let mut todo = todo.clone();
+        todo.completed_at.get_or_insert_with(Utc::now);
+        archived.push(todo);
+    }
+    write_archived_todos(&archived)
+}
+
+/// Parse the lines of the legacy `todos.txt`, the error has the line number.
+fn parse_legacy_todos(content: &str) -> Result<Vec<Todo>, (usize, TodoError)> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| Todo::try_from(line).map_err(|e| (i + 1, e)))
+        .collect()
+}
+
+/// Convert the old `description:status` file into the structured store.
+///
+/// The legacy file is kept with a `.bak` extension so nothing is lost.
+fn migrate_legacy_todos() -> io::Result<TodoStore> {
+    let mut legacy_path = utils::get_data_folder()?;
+    legacy_path.push(constants::NAME_LEGACY_TODOS_FILE);
+
+    let mut store = TodoStore {
+        version: SCHEMA_VERSION,
+        next_id: 1,
+        todos: Vec::new(),
+    };
+    if !legacy_path.exists() {
+        return Ok(store);
+    }
+
+    let content = fs::read_to_string(&legacy_path)?;
+    store.todos = parse_legacy_todos(&content)
+        .map_err(|(line, e)| invalid_data(format!("{}:{}: {}", legacy_path.display(), line, e)))?;
+
+    store.assign_ids();
+    write_store(&store)?;
+    fs::rename(&legacy_path, legacy_path.with_extension("txt.bak"))?;
+    Ok(store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo::todo::{Priority, TodoState};
+
+    #[test]
+    fn legacy_todos_keep_colons_and_skip_blank_lines() {
+        let todos = parse_legacy_todos("Buy milk:0\n\nCall Bob: 10:30:1\n").unwrap();
+        assert_eq!(todos.len(), 2);
+        assert_eq!(todos[0].description, "Buy milk");
+        assert_eq!(todos[0].status, TodoState::NoStarted);
+        assert_eq!(todos[1].description, "Call Bob: 10:30");
+        assert_eq!(todos[1].status, TodoState::Completed);
+    }
+
+    #[test]
+    fn legacy_todos_report_the_wrong_line() {
+        let error = parse_legacy_todos("ok:0\nno status\n").unwrap_err();
+        assert_eq!(error.0, 2);
+        assert!(matches!(error.1, TodoError::MissingData));
+
+        let error = parse_legacy_todos("ok:7\n").unwrap_err();
+        assert!(matches!(error.1, TodoError::UnknownState(_)));
+    }
+
+    #[test]
+    fn ids_are_given_after_the_highest_one() {
+        let mut store = TodoStore {
+            version: 1,
+            next_id: 0,
+            todos: vec![Todo::new("a"), Todo::new("b"), Todo::new("c")],
+        };
+        store.todos[1].id = 5;
+        store.assign_ids();
+        let ids: Vec<u32> = store.todos.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![6, 5, 7]);
+        assert_eq!(store.next_id, 8);
+    }
+
+    #[test]
+    fn archived_ids_are_not_handed_out() {
+        let mut store = TodoStore {
+            version: SCHEMA_VERSION,
+            next_id: 3,
+            todos: vec![Todo::new("a")],
+        };
+        let mut archived = Todo::new("done");
+        archived.id = 6;
+        store.reserve_ids(&[archived]);
+        store.assign_ids();
+        assert_eq!(store.todos[0].id, 7);
+        assert_eq!(store.next_id, 8);
+
+        store.reserve_ids(&[]);
+        assert_eq!(store.next_id, 8);
+    }
+
+    #[test]
+    fn each_todos_file_has_its_archive() {
+        assert_eq!(
+            archive_path_for(Path::new("data/todos.json")),
+            Path::new("data/todos_archive.json")
+        );
+        assert_eq!(
+            archive_path_for(Path::new("lists/work.json")),
+            Path::new("lists/work_archive.json")
+        );
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let path = Path::new("todos.json");
+        assert!(check_version(path, SCHEMA_VERSION).is_ok());
+        assert!(check_version(path, 1).is_ok());
+        let error = check_version(path, SCHEMA_VERSION + 1).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn version_3_todos_are_not_completed_yet() {
+        let content = r#"{"version": 3, "next_id": 2, "todos": [
+            {"id": 1, "description": "a", "status": "completed", "priority": "P1"}
+        ]}"#;
+        let mut store: TodoStore = serde_json::from_str(content).unwrap();
+        store.migrate();
+        assert_eq!(store.version, SCHEMA_VERSION);
+        assert_eq!(store.todos[0].completed_at, None);
+        assert_eq!(store.todos[0].priority, Priority::P1);
+    }
+
+    #[test]
+    fn version_2_stores_get_the_default_metadata() {
+        let content = r#"{"version": 2, "next_id": 3, "todos": [
+            {"id": 1, "description": "a", "status": "completed"},
+            {"id": 2, "description": "b", "status": "not_started"}
+        ]}"#;
+        let mut store: TodoStore = serde_json::from_str(content).unwrap();
+        store.migrate();
+        assert_eq!(store.version, SCHEMA_VERSION);
+        assert_eq!(store.next_id, 3);
+        assert_eq!(store.todos[1].priority, Priority::default());
+        assert!(store.todos[1].tags.is_empty());
+        assert_eq!(store.todos[1].due, None);
+        assert_eq!(store.todos[1].project, None);
+    }
+}
@@ -23,28 +23,23 @@ pub fn table<T: Display + Clone>(mut body: Vec<Vec<T>>, headers: Vec<T>) -> Vec<
     let mut table_content = Vec::new();
 
     for list in &body {
-        let mut i = 0;
-        for item in list {
+        for (i, item) in list.iter().enumerate() {
             let length = count_chars(item);
             if length > max_sizes[i] {
                 max_sizes[i] = length
             }
-            i += 1;
         }
     }
 
     for list in &body {
         let mut new_list = Vec::with_capacity(list.len());
 
-        let mut i = 0;
-
-        for item in list {
+        for (i, item) in list.iter().enumerate() {
             let item_str = &item.to_string();
             new_list.push(fill_text(
-                &item_str,
+                item_str,
                 (max_sizes[i] - count_chars(item_str)) as i32,
             ));
-            i += 1;
         }
         table_content.push(new_list);
     }
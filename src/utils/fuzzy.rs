@@ -0,0 +1,75 @@
+/// Result of matching a query against a text.
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Index (in chars) of each matched character of the text.
+    pub indices: Vec<usize>,
+}
+
+/// Match `query` as a subsequence of `text`, ignoring case.
+///
+/// Consecutive characters and characters at the start of a word score
+/// higher, so `wf` ranks `wflow` above `my-workflow`. Returns `None` when
+/// some character of the query is missing.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let lower_text: Vec<char> = text
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+
+    let mut indices: Vec<usize> = Vec::with_capacity(query.len());
+    let mut score: i64 = 0;
+    let mut text_index = 0;
+
+    for q in &query {
+        let found = lower_text[text_index..].iter().position(|c| c == q)?;
+        let index = text_index + found;
+
+        score += 1;
+        if indices.last().is_some_and(|last| last + 1 == index) {
+            score += 5;
+        }
+        let at_word_start = index == 0 || !text[index - 1].is_alphanumeric();
+        if at_word_start {
+            score += 3;
+        }
+        indices.push(index);
+        text_index = index + 1;
+    }
+
+    // Prefer shorter texts when everything else is equal
+    score -= (text.len() as i64) / 10;
+    Some(FuzzyMatch { score, indices })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(query: &str, text: &str) -> i64 {
+        fuzzy_match(query, text).unwrap().score
+    }
+
+    #[test]
+    fn the_query_is_a_subsequence_ignoring_case() {
+        let m = fuzzy_match("WfL", "my-wflow").unwrap();
+        assert_eq!(m.indices, vec![3, 4, 5]);
+        assert!(fuzzy_match("flw", "wflow").is_some());
+        assert!(fuzzy_match("wolf", "wflow").is_none());
+        assert!(fuzzy_match("ü", "Über").is_some());
+    }
+
+    #[test]
+    fn an_empty_query_matches_everything() {
+        let m = fuzzy_match("", "anything").unwrap();
+        assert!(m.indices.is_empty());
+    }
+
+    #[test]
+    fn word_starts_and_consecutive_characters_rank_higher() {
+        assert!(score("wf", "wflow") > score("wf", "my-workflow"));
+        assert!(score("api", "api-server") > score("api", "a-pi"));
+        assert!(score("app", "app") > score("app", "app-with-a-long-name"));
+    }
+}
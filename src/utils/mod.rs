@@ -1,23 +1,19 @@
+pub mod fuzzy;
+pub mod output;
+
 use std::{
+    collections::hash_map::RandomState,
     env,
-    fs::{self, OpenOptions},
-    io::{self, Read},
+    fs::{self, File, OpenOptions},
+    hash::{BuildHasher, Hasher},
+    io,
     path::{Path, PathBuf},
+    process::Command,
 };
 
-/// This function get the file content of path passed to function
-/// If the file already exist, then get content
-/// otherwise, create the file and return empty String
-pub fn get_content_file<P: AsRef<Path>>(path: P) -> io::Result<String> {
-    let mut file = OpenOptions::new()
-        .read(true)
-        .create(true)
-        .write(true)
-        .open(path)?;
-    let mut content = String::new();
-    file.read_to_string(&mut content)?;
-    Ok(content)
-}
+use crossterm::style::Stylize;
+
+use crate::constants;
 
 /// Write content in the file passed as path to function
 pub fn write_file<P: AsRef<Path>>(path: P, content: &str) -> io::Result<()> {
@@ -25,8 +21,261 @@ pub fn write_file<P: AsRef<Path>>(path: P, content: &str) -> io::Result<()> {
     Ok(())
 }
 
-pub fn get_folder_program() -> io::Result<PathBuf> {
-    let mut path = env::current_exe()?;
-    path.pop();
-    return Ok(path);
+/// Create a new file in the temporary folder, named `<prefix>-<random>.txt`.
+///
+/// The file must not exist yet, so another user cannot prepare it or link it
+/// elsewhere, and only the current user can read it.
+pub fn create_temp_file(prefix: &str) -> io::Result<(PathBuf, File)> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    for _ in 0..16 {
+        // `RandomState` is seeded randomly, which is enough for a file name
+        let suffix = RandomState::new().build_hasher().finish();
+        let path = env::temp_dir().join(format!("{}-{:016x}.txt", prefix, suffix));
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "Cannot create a temporary file",
+    ))
+}
+
+/// Open the file with $VISUAL or $EDITOR, and wait until the editor is closed.
+///
+/// The editor can be a command with arguments, like `code --wait`.
+pub fn edit_file(path: &Path) -> io::Result<()> {
+    let from_env = |name: &str| env::var(name).ok().filter(|e| !e.trim().is_empty());
+    let editor = from_env("VISUAL")
+        .or_else(|| from_env("EDITOR"))
+        .unwrap_or_else(|| {
+            if cfg!(target_os = "windows") {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        });
+    let args = split_command(&editor);
+    let (program, rest) = match args.split_first() {
+        Some(parts) => parts,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The editor command is empty",
+            ))
+        }
+    };
+    let status = Command::new(program).args(rest).arg(path).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} exited with {}",
+            editor, status
+        )));
+    }
+    Ok(())
+}
+
+/// Split a command like `idea --wait "{path}"` into its arguments.
+///
+/// Single and double quotes group words, and a backslash escapes the next
+/// character outside of single quotes.
+pub fn split_command(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('\''), c) => current.push(c),
+            (_, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_arg = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+/// Folder of the executable, where older versions saved their data.
+fn get_folder_program() -> io::Result<Vec<PathBuf>> {
+    let exe = env::current_exe()?;
+    let mut folders = Vec::new();
+    for path in [Some(exe.clone()), exe.canonicalize().ok()]
+        .into_iter()
+        .flatten()
+    {
+        if let Some(parent) = path.parent() {
+            if !folders.iter().any(|f: &PathBuf| f == parent) {
+                folders.push(parent.to_path_buf());
+            }
+        }
+    }
+    Ok(folders)
+}
+
+/// Resolve a per-user folder of wflow.
+///
+/// `env_override` is used as is when set, then `$<xdg_var>/wflow`, then
+/// `~/<home_default>/wflow`.
+fn get_user_folder_path(
+    env_override: &str,
+    xdg_var: &str,
+    home_default: &[&str],
+) -> io::Result<PathBuf> {
+    if let Some(path) = env::var_os(env_override).filter(|p| !p.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    if let Some(path) = env::var_os(xdg_var).filter(|p| !p.is_empty()) {
+        return Ok(Path::new(&path).join(constants::PROGRAM_FOLDER_NAME));
+    }
+    if cfg!(target_os = "windows") {
+        if let Some(path) = env::var_os("APPDATA").filter(|p| !p.is_empty()) {
+            return Ok(Path::new(&path).join(constants::PROGRAM_FOLDER_NAME));
+        }
+    }
+    match get_home_folder() {
+        Some(home) => {
+            let mut path = home;
+            path.extend(home_default);
+            path.push(constants::PROGRAM_FOLDER_NAME);
+            Ok(path)
+        }
+        None => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "Cannot find the wflow folders, set {} or HOME",
+                env_override
+            ),
+        )),
+    }
+}
+
+pub fn get_home_folder() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Expand a leading `~` to the home folder.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), get_home_folder()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            home.join(rest.trim_start_matches(['/', '\\']))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Get the folder where wflow saves its data, creating it if needed.
+///
+/// `WFLOW_DATA_DIR` is used when set, otherwise `$XDG_DATA_HOME/wflow`
+/// or `~/.local/share/wflow`.
+pub fn get_data_folder() -> io::Result<PathBuf> {
+    let path = get_user_folder_path(
+        constants::ENV_DATA_DIR,
+        "XDG_DATA_HOME",
+        &[".local", "share"],
+    )?;
+    fs::create_dir_all(&path)?;
+    migrate_from_folder_program(&path)?;
+    Ok(path)
+}
+
+/// Get the folder of the configuration files, without creating it.
+///
+/// `WFLOW_CONFIG_DIR` is used when set, otherwise `$XDG_CONFIG_HOME/wflow`
+/// or `~/.config/wflow`.
+pub fn get_config_folder() -> io::Result<PathBuf> {
+    get_user_folder_path(constants::ENV_CONFIG_DIR, "XDG_CONFIG_HOME", &[".config"])
+}
+
+/// Move the data files saved next to the executable into the data folder.
+///
+/// Files that already exist in the data folder are never overwritten.
+fn migrate_from_folder_program(data_folder: &Path) -> io::Result<()> {
+    for folder in get_folder_program()? {
+        if folder == data_folder {
+            continue;
+        }
+        for name in constants::DATA_FILES {
+            let old_path = folder.join(name);
+            let new_path = data_folder.join(name);
+            if !old_path.is_file() || new_path.exists() {
+                continue;
+            }
+            // `rename` fails across file systems, so fall back to copying
+            if fs::rename(&old_path, &new_path).is_err() {
+                fs::copy(&old_path, &new_path)?;
+                // The data is safe, a read-only folder must not stop the command
+                if let Err(e) = fs::remove_file(&old_path) {
+                    eprintln!(
+                        "{} {} was copied to {} but cannot be removed: {}",
+                        "Warning:".yellow(),
+                        old_path.display(),
+                        new_path.display(),
+                        e
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temp_files_are_new_and_unique() {
+        let (first, _) = create_temp_file("wflow-test").unwrap();
+        let (second, _) = create_temp_file("wflow-test").unwrap();
+        assert_ne!(first, second);
+        assert!(first.is_file());
+        fs::remove_file(first).unwrap();
+        fs::remove_file(second).unwrap();
+    }
+
+    #[test]
+    fn commands_are_split_on_spaces_outside_quotes() {
+        assert_eq!(split_command("code --wait"), vec!["code", "--wait"]);
+        assert_eq!(
+            split_command(r#"idea  "my files/{path}" 'a b'"#),
+            vec!["idea", "my files/{path}", "a b"]
+        );
+        assert_eq!(split_command(r"vim a\ b"), vec!["vim", "a b"]);
+        assert_eq!(split_command(r"echo 'a\b'"), vec!["echo", r"a\b"]);
+        assert_eq!(split_command(r#"echo """#), vec!["echo", ""]);
+        assert!(split_command("   ").is_empty());
+    }
 }
@@ -0,0 +1,87 @@
+use std::io::{self, Stdout, Write};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// Format of the output meant to be read by other programs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// An array of objects
+    Json,
+    /// Comma-separated values, with a header row
+    Csv,
+    /// Tab-separated values, one record per line and no header
+    Plain,
+}
+
+/// A row of a list printed with `print_records`.
+///
+/// The JSON keys and the CSV headers are part of the public interface of
+/// wflow, so they must not change between versions.
+pub trait Record: Serialize {
+    const FIELDS: &'static [&'static str];
+
+    /// Values of the fields, in the same order as `FIELDS`.
+    fn values(&self) -> Vec<String>;
+}
+
+pub fn print_records<R: Record>(
+    stdout: &mut Stdout,
+    format: OutputFormat,
+    records: &[R],
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Json => {
+            let content = serde_json::to_string_pretty(records)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+            writeln!(stdout, "{}", content)?;
+        }
+        OutputFormat::Csv => {
+            writeln!(stdout, "{}", R::FIELDS.join(","))?;
+            for record in records {
+                let values: Vec<String> = record.values().iter().map(|v| csv_field(v)).collect();
+                writeln!(stdout, "{}", values.join(","))?;
+            }
+        }
+        OutputFormat::Plain => {
+            for record in records {
+                let values: Vec<String> = record.values().iter().map(|v| plain_field(v)).collect();
+                writeln!(stdout, "{}", values.join("\t"))?;
+            }
+        }
+    }
+    stdout.flush()
+}
+
+/// Quote the value when it contains a separator, a quote or a line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Tabs and line breaks would break the columns, so they become spaces.
+fn plain_field(value: &str) -> String {
+    value.replace(['\t', '\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("plain text"), "plain text");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn plain_fields_stay_on_one_column() {
+        assert_eq!(plain_field("a\tb\nc\r"), "a b c ");
+    }
+}